@@ -0,0 +1,188 @@
+//! Benchmarks of mock proving, keygen, proving and verification for the example circuits.
+//!
+//! The three ways of computing x^2 + 72 in `examples/halo2_lib.rs` are benchmarked separately, next to the same function
+//! written with the vanilla Halo2 API in `StandardPlonk`, so the cost of the different APIs can be compared.
+//!
+//! The `parallel` group times witness generation of `examples/parallel.rs` on a single rayon thread and on all available threads,
+//! to show the speedup of assigning independent `Context`s in parallel.
+//!
+//! Run with `cargo bench --bench circuits`. To catch regressions, e.g. when bumping `halo2-lib`, save a baseline with
+//! `cargo bench --bench circuits -- --save-baseline main` and compare against it later with `-- --baseline main`.
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_base::{
+    gates::{
+        circuit::{builder::BaseCircuitBuilder, CircuitBuilderStage},
+        flex_gate::MultiPhaseThreadBreakPoints,
+        GateChip,
+    },
+    halo2_proofs::{
+        circuit::Value,
+        dev::MockProver,
+        halo2curves::bn256::{Bn256, Fr},
+        plonk::{keygen_pk, keygen_vk},
+        poly::kzg::commitment::ParamsKZG,
+    },
+    utils::fs::gen_srs,
+    AssignedValue, Context,
+};
+use halo2_scaffold::{
+    scaffold::{
+        create_circuit, pinning::MultiOpenScheme, vanilla::VanillaCircuit, verify::verify_snark,
+        ScaffoldCircuit,
+    },
+    vanilla_circuits::standard_plonk::StandardPlonk,
+};
+use snark_verifier_sdk::halo2::gen_snark_shplonk;
+
+#[allow(dead_code)]
+#[path = "../examples/halo2_lib.rs"]
+mod halo2_lib;
+#[allow(dead_code)]
+#[path = "../examples/parallel.rs"]
+mod parallel;
+#[allow(dead_code)]
+#[path = "../examples/poseidon.rs"]
+mod poseidon;
+#[allow(dead_code)]
+#[path = "../examples/range.rs"]
+mod range;
+
+const K: u32 = 10;
+const LOOKUP_BITS: usize = 8;
+
+/// Benchmarks each step of the lifecycle of the circuits returned by `create_circuit`.
+fn bench_circuit<C: ScaffoldCircuit>(
+    c: &mut Criterion,
+    name: &str,
+    lookup_bits: Option<usize>,
+    create_circuit: impl Fn(
+        CircuitBuilderStage,
+        Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+        &ParamsKZG<Bn256>,
+    ) -> C,
+) where
+    C::Params: Clone,
+{
+    // the scaffold reads the lookup bits from the env var when creating a circuit without a pinning
+    match lookup_bits {
+        Some(lookup_bits) => std::env::set_var("LOOKUP_BITS", lookup_bits.to_string()),
+        None => std::env::remove_var("LOOKUP_BITS"),
+    }
+    let params = gen_srs(K);
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    group.bench_function("mock", |b| {
+        b.iter(|| {
+            let mut circuit = create_circuit(CircuitBuilderStage::Mock, None, &params);
+            MockProver::run(K, &circuit, circuit.instances()).unwrap().assert_satisfied();
+            circuit.clear();
+        })
+    });
+
+    let keygen = || {
+        let mut circuit = create_circuit(CircuitBuilderStage::Keygen, None, &params);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+        let pinning = (circuit.params(), circuit.break_points());
+        circuit.clear();
+        (pk, pinning)
+    };
+    group.bench_function("keygen", |b| b.iter(&keygen));
+
+    let (pk, pinning) = keygen();
+    let prove = || {
+        let circuit = create_circuit(CircuitBuilderStage::Prover, Some(pinning.clone()), &params);
+        gen_snark_shplonk(&params, &pk, circuit, None::<&str>)
+    };
+    group.bench_function("prove", |b| b.iter(&prove));
+
+    let snark = prove();
+    group.bench_function("verify", |b| {
+        b.iter(|| verify_snark(&params, pk.get_vk(), &snark, MultiOpenScheme::Shplonk).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_x_sq_plus_72(c: &mut Criterion) {
+    let x = Fr::from(12);
+    let gate = GateChip::default();
+    let variants: [(&str, &dyn Fn(&mut Context<Fr>, AssignedValue<Fr>) -> AssignedValue<Fr>); 3] = [
+        ("halo2_lib_gate", &|ctx: &mut Context<Fr>, x| halo2_lib::x_sq_plus_72_gate(ctx, &gate, x)),
+        ("halo2_lib_region", &halo2_lib::x_sq_plus_72_region::<Fr>),
+        ("halo2_lib_mul_add", &|ctx: &mut Context<Fr>, x| {
+            halo2_lib::x_sq_plus_72_mul_add(ctx, &gate, x)
+        }),
+    ];
+    for (name, x_sq_plus_72) in variants {
+        let f = |builder: &mut BaseCircuitBuilder<Fr>,
+                 x: Fr,
+                 make_public: &mut Vec<AssignedValue<Fr>>| {
+            let ctx = builder.main(0);
+            let x = ctx.load_witness(x);
+            let out = x_sq_plus_72(ctx, x);
+            make_public.extend([x, out]);
+        };
+        bench_circuit(c, &format!("x_sq_plus_72/{name}"), None, |stage, pinning, params| {
+            create_circuit(f, x, stage, pinning, params)
+        });
+    }
+    bench_circuit(c, "x_sq_plus_72/vanilla", None, |_, _, _| {
+        VanillaCircuit(StandardPlonk { x: Value::known(x) })
+    });
+}
+
+fn bench_examples(c: &mut Criterion) {
+    let input = range::CircuitInput { x: "18446744073709551615".to_string() };
+    bench_circuit(c, "range", Some(LOOKUP_BITS), |stage, pinning, params| {
+        create_circuit(range::some_algorithm_in_zk, input.clone(), stage, pinning, params)
+    });
+    let input = poseidon::CircuitInput { inputs: ["6".to_string(), "100".to_string()] };
+    bench_circuit(c, "poseidon", None, |stage, pinning, params| {
+        create_circuit(poseidon::hash_two, input.clone(), stage, pinning, params)
+    });
+}
+
+fn bench_parallel(c: &mut Criterion) {
+    // large enough that witness generation dominates the time to spawn the threads
+    const K_PARALLEL: u32 = 15;
+    std::env::remove_var("LOOKUP_BITS");
+    let params = gen_srs(K_PARALLEL);
+    let input = parallel::CircuitInput { x: "12".to_string(), num_hashes: 100 };
+    let mut circuit = create_circuit(
+        parallel::hash_many,
+        input.clone(),
+        CircuitBuilderStage::Keygen,
+        None,
+        &params,
+    );
+    // synthesizing the circuit computes the break points
+    keygen_vk(&params, &circuit).unwrap();
+    let pinning = (circuit.params(), circuit.break_points());
+    circuit.clear();
+
+    let max_threads = std::thread::available_parallelism().map_or(1, usize::from);
+    let mut group = c.benchmark_group("parallel");
+    group.sample_size(10);
+    for num_threads in [1, max_threads] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(num_threads).build().unwrap();
+        group.bench_function(format!("witness_gen/{num_threads}_threads"), |b| {
+            b.iter(|| {
+                pool.install(|| {
+                    let mut circuit = create_circuit(
+                        parallel::hash_many,
+                        input.clone(),
+                        CircuitBuilderStage::Prover,
+                        Some(pinning.clone()),
+                        &params,
+                    );
+                    circuit.clear();
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_x_sq_plus_72, bench_examples, bench_parallel);
+criterion_main!(benches);
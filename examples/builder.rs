@@ -2,7 +2,6 @@
 use clap::Parser;
 use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
 use halo2_base::gates::{GateChip, GateInstructions};
-use halo2_base::halo2_proofs::halo2curves::{bn256::Fr, ff::Field};
 use halo2_base::utils::ScalarField;
 use halo2_base::AssignedValue;
 #[allow(unused_imports)]
@@ -11,11 +10,10 @@ use halo2_base::{
     QuantumCell::{Constant, Existing, Witness},
 };
 use halo2_scaffold::scaffold::cmd::Cli;
-use halo2_scaffold::scaffold::run_on_inputs;
-use rand::rngs::OsRng;
+use halo2_scaffold::scaffold::run_with_generator;
 
 // this algorithm takes a public input x, computes x^2 + 72, and outputs the result as public output
-fn some_algorithm_in_zk<F: ScalarField>(
+pub fn some_algorithm_in_zk<F: ScalarField>(
     builder: &mut BaseCircuitBuilder<F>,
     x: F,
     make_public: &mut Vec<AssignedValue<F>>,
@@ -24,7 +22,7 @@ fn some_algorithm_in_zk<F: ScalarField>(
     let ctx = builder.main(0); // 0 means FirstPhase, don't worry about it
 
     // `Context` can roughly be thought of as a single-threaded execution trace of a program we want to ZK prove. We do some post-processing on `Context` to optimally divide the execution trace into multiple columns in a PLONKish arithmetization
-    // More advanced usage with multi-threaded witness generation is possible, but we do not explain it here
+    // For multi-threaded witness generation, where `builder` creates more `Context`s that are assigned in parallel, see `examples/parallel.rs`
 
     // first we load a number `x` into as system, as a "witness"
     let x = ctx.load_witness(x);
@@ -72,7 +70,7 @@ fn main() {
     let args = Cli::parse();
 
     // let's say we don't want to run prover with inputs from file
-    // instead we generate inputs here:
-    let private_inputs = Fr::random(OsRng);
-    run_on_inputs(some_algorithm_in_zk, args, private_inputs);
+    // the input `x` is a field element, which implements `GenerateInput`, so without a `data/builder.in` file
+    // every command runs on a random `x`. `gen-input` writes random inputs to files instead
+    run_with_generator(some_algorithm_in_zk, args);
 }
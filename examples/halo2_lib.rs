@@ -1,15 +1,16 @@
 use clap::Parser;
 use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
 use halo2_base::gates::{GateChip, GateInstructions};
-use halo2_base::utils::ScalarField;
+use halo2_base::halo2_proofs::halo2curves::{bn256::Fr, ff::Field};
+use halo2_base::utils::{fe_to_biguint, ScalarField};
 use halo2_base::AssignedValue;
-#[allow(unused_imports)]
 use halo2_base::{
     Context,
     QuantumCell::{Constant, Existing, Witness},
 };
 use halo2_scaffold::scaffold::cmd::Cli;
-use halo2_scaffold::scaffold::run;
+use halo2_scaffold::scaffold::{input::GenerateInput, run_with_generator};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -17,8 +18,15 @@ pub struct CircuitInput {
     pub x: String, // field element, but easier to deserialize as a string
 }
 
+// lets `gen-input` write random inputs, and commands run on a random input if there is no input file
+impl GenerateInput for CircuitInput {
+    fn generate(rng: &mut impl RngCore) -> Self {
+        Self { x: fe_to_biguint(&Fr::random(rng)).to_string() }
+    }
+}
+
 // this algorithm takes a public input x, computes x^2 + 72, and outputs the result as public output
-fn some_algorithm_in_zk<F: ScalarField>(
+pub fn some_algorithm_in_zk<F: ScalarField>(
     builder: &mut BaseCircuitBuilder<F>,
     input: CircuitInput,
     make_public: &mut Vec<AssignedValue<F>>,
@@ -38,7 +46,28 @@ fn some_algorithm_in_zk<F: ScalarField>(
     let gate = GateChip::<F>::default();
 
     // ===== way 1 =====
-    // now we can perform arithmetic operations almost like a normal program using halo2-lib API functions
+    let out = x_sq_plus_72_gate(ctx, &gate, x);
+    // Halo2 does not distinguish between public inputs vs outputs because the verifier seems them all at the same time
+    // However in traditional terms, `out` is our output number. It is currently still private.
+    // Let's make it public:
+    make_public.push(out);
+    // ==== way 2 =======
+    let _val_assigned = x_sq_plus_72_region(ctx, x);
+
+    // ==== way 3 ======
+    let _val_assigned = x_sq_plus_72_mul_add(ctx, &gate, x);
+
+    println!("x: {:?}", x.value());
+    println!("val_assigned: {:?}", out.value());
+    assert_eq!(*x.value() * x.value() + F::from(72), *out.value());
+}
+
+// way 1: now we can perform arithmetic operations almost like a normal program using halo2-lib API functions
+pub fn x_sq_plus_72_gate<F: ScalarField>(
+    ctx: &mut Context<F>,
+    gate: &GateChip<F>,
+    x: AssignedValue<F>,
+) -> AssignedValue<F> {
     // square x
     let x_sq = gate.mul(ctx, x, x);
 
@@ -46,26 +75,28 @@ fn some_algorithm_in_zk<F: ScalarField>(
     let c = F::from(72);
     // the implicit type of most variables is an "Existing" assigned value
     // a known constant is a separate type that we specify by `Constant(c)`:
-    let out = gate.add(ctx, x_sq, Constant(c));
-    // Halo2 does not distinguish between public inputs vs outputs because the verifier seems them all at the same time
-    // However in traditional terms, `out` is our output number. It is currently still private.
-    // Let's make it public:
-    make_public.push(out);
-    // ==== way 2 =======
-    // here is a more optimal way to compute x^2 + 72 using the lower level `assign_region` API:
+    gate.add(ctx, x_sq, Constant(c))
+}
+
+// way 2: here is a more optimal way to compute x^2 + 72 using the lower level `assign_region` API
+pub fn x_sq_plus_72_region<F: ScalarField>(
+    ctx: &mut Context<F>,
+    x: AssignedValue<F>,
+) -> AssignedValue<F> {
+    let c = F::from(72);
     let val = *x.value() * x.value() + c;
-    let _val_assigned =
-        ctx.assign_region_last([Constant(c), Existing(x), Existing(x), Witness(val)], [0]);
     // the `[0]` tells us to turn on a vertical `a + b * c = d` gate at row position 0.
     // this imposes the constraint c + x * x = val
+    ctx.assign_region_last([Constant(c), Existing(x), Existing(x), Witness(val)], [0])
+}
 
-    // ==== way 3 ======
-    // this does the exact same thing as way 2, but with a pre-existing function
-    let _val_assigned = gate.mul_add(ctx, x, x, Constant(c));
-
-    println!("x: {:?}", x.value());
-    println!("val_assigned: {:?}", out.value());
-    assert_eq!(*x.value() * x.value() + c, *out.value());
+// way 3: this does the exact same thing as way 2, but with a pre-existing function
+pub fn x_sq_plus_72_mul_add<F: ScalarField>(
+    ctx: &mut Context<F>,
+    gate: &GateChip<F>,
+    x: AssignedValue<F>,
+) -> AssignedValue<F> {
+    gate.mul_add(ctx, x, x, Constant(F::from(72)))
 }
 
 fn main() {
@@ -74,5 +105,5 @@ fn main() {
     let args = Cli::parse();
 
     // run different zk commands based on the command line arguments
-    run(some_algorithm_in_zk, args);
+    run_with_generator(some_algorithm_in_zk, args);
 }
@@ -0,0 +1,47 @@
+//! Example of a circuit with more than one instance column
+use clap::Parser;
+use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
+use halo2_base::gates::{GateChip, GateInstructions};
+use halo2_base::utils::ScalarField;
+use halo2_base::AssignedValue;
+use halo2_base::QuantumCell::Constant;
+use halo2_scaffold::scaffold::cmd::Cli;
+use halo2_scaffold::scaffold::run;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub x: String, // field element, but easier to deserialize as a string
+}
+
+// this algorithm takes a public input x, computes x^2 + 72, and outputs the result as public output
+// the input and output are placed in two separate instance columns
+fn some_algorithm_in_zk<F: ScalarField>(
+    builder: &mut BaseCircuitBuilder<F>,
+    input: CircuitInput,
+    make_public: &mut [Vec<AssignedValue<F>>; 2],
+) {
+    let x = F::from_str_vartime(&input.x).expect("deserialize field element should not fail");
+    let ctx = builder.main(0);
+
+    let x = ctx.load_witness(x);
+    // the number of instance columns is determined by the type of `make_public`
+    // here `make_public[0]` is the first instance column and `make_public[1]` is the second
+    make_public[0].push(x);
+
+    let gate = GateChip::<F>::default();
+    let out = gate.mul_add(ctx, x, x, Constant(F::from(72)));
+    make_public[1].push(out);
+
+    println!("x: {:?}", x.value());
+    println!("out: {:?}", out.value());
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Cli::parse();
+
+    // run different zk commands based on the command line arguments
+    run(some_algorithm_in_zk, args);
+}
@@ -0,0 +1,27 @@
+//! Runs the vanilla Halo2 `OrCircuit` from `src/vanilla_circuits/or.rs` through the scaffold CLI
+use clap::Parser;
+use halo2_base::halo2_proofs::{circuit::Value, halo2curves::bn256::Fr};
+use halo2_scaffold::scaffold::{cmd::Cli, vanilla::run_vanilla};
+use halo2_scaffold::vanilla_circuits::or::OrCircuit;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub a: bool,
+    pub b: bool,
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Cli::parse();
+
+    // the circuit is created from the private inputs read from `data/or.in`
+    run_vanilla(
+        |input: CircuitInput| OrCircuit {
+            a: Value::known(Fr::from(input.a as u64)),
+            b: Value::known(Fr::from(input.b as u64)),
+        },
+        args,
+    );
+}
@@ -0,0 +1,58 @@
+//! Example of multi-threaded witness generation, where independent parts of the computation are assigned in separate `Context`s in parallel
+use clap::Parser;
+use halo2_base::{
+    gates::{circuit::builder::BaseCircuitBuilder, GateChip, GateInstructions},
+    poseidon::hasher::PoseidonHasher,
+    utils::BigPrimeField,
+    AssignedValue,
+};
+use halo2_scaffold::scaffold::{cmd::Cli, parallelize, run};
+use serde::{Deserialize, Serialize};
+use snark_verifier_sdk::halo2::OptimizedPoseidonSpec;
+
+const T: usize = 3;
+const RATE: usize = 2;
+const R_F: usize = 8;
+const R_P: usize = 57;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub x: String, // field element, but easier to deserialize as a string
+    pub num_hashes: usize,
+}
+
+// this algorithm computes poseidon(x, i) for i in 0..num_hashes and outputs x and the sum of all the hashes as public output
+pub fn hash_many<F: BigPrimeField>(
+    builder: &mut BaseCircuitBuilder<F>,
+    input: CircuitInput,
+    make_public: &mut Vec<AssignedValue<F>>,
+) {
+    let x = F::from_str_vartime(&input.x).expect("deserialize field element should not fail");
+    let x = builder.main(0).load_witness(x);
+    make_public.push(x);
+
+    let gate = GateChip::<F>::default();
+    // computing the round constants is expensive, so we only do it once and share the spec between threads
+    let spec = OptimizedPoseidonSpec::<F, T, RATE>::new::<R_F, R_P, 0>();
+    // each hash is independent of the others, so each one gets its own `Context` and they are all computed in parallel
+    // `x` was assigned in the main thread: using it in another thread just creates a copy constraint
+    let hashes = parallelize(builder, 0, (0..input.num_hashes).collect(), |ctx, i| {
+        let mut poseidon = PoseidonHasher::<F, T, RATE>::new(spec.clone());
+        poseidon.initialize_consts(ctx, &gate);
+        let i = ctx.load_constant(F::from(i as u64));
+        poseidon.hash_fix_len_array(ctx, &gate, &[x, i])
+    });
+
+    // back in the main thread, we can use the results of all the threads
+    let ctx = builder.main(0);
+    let sum = gate.sum(ctx, hashes);
+    make_public.push(sum);
+    println!("x: {:?}, sum of hashes: {:?}", x.value(), sum.value());
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Cli::parse();
+    run(hash_many, args);
+}
@@ -1,11 +1,13 @@
 use clap::Parser;
 use halo2_base::{
     gates::{circuit::builder::BaseCircuitBuilder, GateChip},
+    halo2_proofs::halo2curves::{bn256::Fr, ff::Field},
     poseidon::hasher::PoseidonHasher,
-    utils::BigPrimeField,
+    utils::{fe_to_biguint, BigPrimeField},
     AssignedValue,
 };
-use halo2_scaffold::scaffold::{cmd::Cli, run};
+use halo2_scaffold::scaffold::{cmd::Cli, input::GenerateInput, run_with_generator};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use snark_verifier_sdk::halo2::OptimizedPoseidonSpec;
 
@@ -19,7 +21,13 @@ pub struct CircuitInput {
     pub inputs: [String; 2], // two field elements, but as strings for easier deserialization
 }
 
-fn hash_two<F: BigPrimeField>(
+impl GenerateInput for CircuitInput {
+    fn generate(rng: &mut impl RngCore) -> Self {
+        Self { inputs: [(); 2].map(|_| fe_to_biguint(&Fr::random(&mut *rng)).to_string()) }
+    }
+}
+
+pub fn hash_two<F: BigPrimeField>(
     builder: &mut BaseCircuitBuilder<F>,
     inp: CircuitInput,
     make_public: &mut Vec<AssignedValue<F>>,
@@ -46,5 +54,5 @@ fn main() {
     env_logger::init();
 
     let args = Cli::parse();
-    run(hash_two, args);
+    run_with_generator(hash_two, args);
 }
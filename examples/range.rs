@@ -4,7 +4,8 @@ use halo2_base::gates::{GateInstructions, RangeInstructions};
 use halo2_base::utils::ScalarField;
 use halo2_base::AssignedValue;
 use halo2_scaffold::scaffold::cmd::Cli;
-use halo2_scaffold::scaffold::run;
+use halo2_scaffold::scaffold::{input::GenerateInput, run_with_generator};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -12,7 +13,14 @@ pub struct CircuitInput {
     pub x: String, // field element, but easier to deserialize as a string
 }
 
-fn some_algorithm_in_zk<F: ScalarField>(
+impl GenerateInput for CircuitInput {
+    // valid inputs are less than 2^64
+    fn generate(rng: &mut impl RngCore) -> Self {
+        Self { x: rng.next_u64().to_string() }
+    }
+}
+
+pub fn some_algorithm_in_zk<F: ScalarField>(
     builder: &mut BaseCircuitBuilder<F>,
     input: CircuitInput,
     make_public: &mut Vec<AssignedValue<F>>,
@@ -43,5 +51,5 @@ fn main() {
     let args = Cli::parse();
 
     // run different zk commands based on the command line arguments
-    run(some_algorithm_in_zk, args);
+    run_with_generator(some_algorithm_in_zk, args);
 }
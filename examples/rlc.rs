@@ -0,0 +1,76 @@
+//! Example of a two phase circuit that uses a random linear combination (RLC) to check that two variable length byte arrays are equal
+use clap::Parser;
+use halo2_base::gates::circuit::builder::BaseCircuitBuilder;
+use halo2_base::gates::{GateChip, RangeInstructions};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+use halo2_base::AssignedValue;
+use halo2_scaffold::scaffold::cmd::Cli;
+use halo2_scaffold::scaffold::rlc::{run_rlc, RlcChip};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    /// Bytes padded to a fixed maximum length; only the first `a_len` are used
+    pub a: Vec<u8>,
+    pub a_len: usize,
+    pub b: Vec<u8>,
+    pub b_len: usize,
+}
+
+/// Values assigned in the first phase that are needed in the second phase
+#[derive(Clone, Debug)]
+pub struct Payload {
+    a: Vec<AssignedValue<Fr>>,
+    a_len: AssignedValue<Fr>,
+    b: Vec<AssignedValue<Fr>>,
+    b_len: AssignedValue<Fr>,
+}
+
+// the first phase loads the bytes and lengths, and range checks them
+fn load_bytes(
+    builder: &mut BaseCircuitBuilder<Fr>,
+    input: CircuitInput,
+    make_public: &mut Vec<AssignedValue<Fr>>,
+) -> Payload {
+    let range = builder.range_chip();
+    let ctx = builder.main(0);
+
+    let [a, b] = [input.a, input.b].map(|bytes| {
+        let bytes = ctx.assign_witnesses(bytes.into_iter().map(|byte| Fr::from(byte as u64)));
+        for byte in &bytes {
+            range.range_check(ctx, *byte, 8);
+        }
+        bytes
+    });
+    let a_len = ctx.load_witness(Fr::from(input.a_len as u64));
+    let b_len = ctx.load_witness(Fr::from(input.b_len as u64));
+    // the lengths can be at most the padded lengths
+    range.check_less_than_safe(ctx, a_len, a.len() as u64 + 1);
+    range.check_less_than_safe(ctx, b_len, b.len() as u64 + 1);
+    ctx.constrain_equal(&a_len, &b_len);
+    make_public.push(a_len);
+
+    Payload { a, a_len, b, b_len }
+}
+
+// the second phase has access to the challenge `gamma`, which is sampled after the bytes are committed to
+fn check_equal(builder: &mut BaseCircuitBuilder<Fr>, rlc: &RlcChip, payload: Payload) {
+    let gate = GateChip::default();
+    let ctx = builder.main(1);
+
+    let rlc_a = rlc.compute_rlc_var_len(ctx, &gate, &payload.a, payload.a_len);
+    let rlc_b = rlc.compute_rlc_var_len(ctx, &gate, &payload.b, payload.b_len);
+    // with overwhelming probability over `gamma`, equal RLCs of equal length byte arrays mean the byte arrays are equal
+    ctx.constrain_equal(&rlc_a, &rlc_b);
+
+    println!("rlc: {:?}", rlc_a.value());
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Cli::parse();
+
+    // run different zk commands based on the command line arguments
+    run_rlc(load_bytes, check_equal, args);
+}
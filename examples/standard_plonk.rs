@@ -0,0 +1,30 @@
+//! Runs the vanilla Halo2 `StandardPlonk` circuit from `src/vanilla_circuits/standard_plonk.rs` through the scaffold CLI
+use clap::Parser;
+use halo2_base::halo2_proofs::{
+    circuit::Value,
+    halo2curves::{bn256::Fr, ff::PrimeField},
+};
+use halo2_scaffold::scaffold::{cmd::Cli, vanilla::run_vanilla};
+use halo2_scaffold::vanilla_circuits::standard_plonk::StandardPlonk;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub x: String, // field element, but easier to deserialize as a string
+}
+
+fn main() {
+    env_logger::init();
+
+    let args = Cli::parse();
+
+    // the circuit is created from the private inputs read from `data/standard_plonk.in`
+    run_vanilla(
+        |input: CircuitInput| {
+            let x =
+                Fr::from_str_vartime(&input.x).expect("deserialize field element should not fail");
+            StandardPlonk { x: Value::known(x) }
+        },
+        args,
+    );
+}
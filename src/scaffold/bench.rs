@@ -0,0 +1,226 @@
+//! Repeated runs of keygen, witness generation, proving and verification, for comparing the cost of circuits and multi-open schemes.
+use std::{
+    fmt,
+    fs::File,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use ark_std::{end_timer, start_timer};
+use halo2_base::{
+    gates::{circuit::CircuitBuilderStage, flex_gate::MultiPhaseThreadBreakPoints},
+    halo2_proofs::{
+        halo2curves::bn256::Bn256,
+        plonk::{keygen_pk, keygen_vk},
+        poly::{commitment::Params, kzg::commitment::ParamsKZG},
+        SerdeFormat,
+    },
+};
+use serde::{Deserialize, Serialize};
+use snark_verifier_sdk::halo2::{gen_snark_gwc, gen_snark_shplonk};
+
+use super::{pinning::MultiOpenScheme, verify::verify_snark, ScaffoldCircuit};
+
+/// Mean, minimum and maximum of a series of timings, in milliseconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Timing {
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Timing {
+    pub fn new(samples: &[Duration]) -> Self {
+        assert!(!samples.is_empty(), "Timing needs at least one sample");
+        let ms = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect::<Vec<_>>();
+        Self {
+            mean_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+            min_ms: ms.iter().copied().fold(f64::INFINITY, f64::min),
+            max_ms: ms.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} ms (min {:.1}, max {:.1})", self.mean_ms, self.min_ms, self.max_ms)
+    }
+}
+
+/// Timings of the steps that depend on the multi-open scheme.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemeBench {
+    pub scheme: MultiOpenScheme,
+    pub witness_generation: Timing,
+    pub proving: Timing,
+    pub verification: Timing,
+    /// Size of the proof in bytes
+    pub proof_size: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub name: String,
+    pub k: u32,
+    pub runs: usize,
+    pub keygen_vk: Timing,
+    pub keygen_pk: Timing,
+    /// Size of the proving key in bytes, in `SerdeFormat::RawBytes`
+    pub pk_size: usize,
+    /// Size of the verifying key in bytes, in `SerdeFormat::RawBytes`
+    pub vk_size: usize,
+    pub schemes: Vec<SchemeBench>,
+    /// Peak resident set size of the process in kB, only available on Linux
+    pub peak_rss_kb: Option<u64>,
+}
+
+impl BenchReport {
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        serde_json::to_writer_pretty(file, self).expect("Could not write bench report");
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Circuit: {} (k = {}, {} runs)", self.name, self.k, self.runs)?;
+        writeln!(f, "Keygen vk: {}", self.keygen_vk)?;
+        writeln!(f, "Keygen pk: {}", self.keygen_pk)?;
+        writeln!(f, "Proving key size: {} bytes", self.pk_size)?;
+        writeln!(f, "Verifying key size: {} bytes", self.vk_size)?;
+        for bench in &self.schemes {
+            writeln!(f, "{}:", bench.scheme)?;
+            writeln!(f, "  witness generation: {}", bench.witness_generation)?;
+            writeln!(f, "  proving: {}", bench.proving)?;
+            writeln!(f, "  verification: {}", bench.verification)?;
+            writeln!(f, "  proof size: {} bytes", bench.proof_size)?;
+        }
+        match self.peak_rss_kb {
+            Some(rss) => write!(f, "Peak RSS: {rss} kB"),
+            None => write!(f, "Peak RSS: unavailable"),
+        }
+    }
+}
+
+/// Runs keygen `runs` times, then witness generation, proving and verification `runs` times for each of `schemes`.
+///
+/// Each step is wrapped in an `ark_std` timer, so the steps show up in the `print-trace` output next to the timers of
+/// `halo2_proofs` when the `profile` feature is enabled.
+pub fn bench<C: ScaffoldCircuit>(
+    create_circuit: impl Fn(
+        CircuitBuilderStage,
+        Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+        &ParamsKZG<Bn256>,
+    ) -> C,
+    name: &str,
+    params: &ParamsKZG<Bn256>,
+    runs: usize,
+    schemes: &[MultiOpenScheme],
+) -> BenchReport
+where
+    C::Params: Clone,
+{
+    assert!(runs > 0, "Number of runs must be positive");
+    let mut keygen_vk_times = Vec::with_capacity(runs);
+    let mut keygen_pk_times = Vec::with_capacity(runs);
+    let mut keygen = None;
+    for _ in 0..runs {
+        let mut circuit = create_circuit(CircuitBuilderStage::Keygen, None, params);
+        let timer = start_timer!(|| "Keygen vk");
+        let start = Instant::now();
+        let vk = keygen_vk(params, &circuit).expect("Verifying key generation should not fail");
+        keygen_vk_times.push(start.elapsed());
+        end_timer!(timer);
+
+        let timer = start_timer!(|| "Keygen pk");
+        let start = Instant::now();
+        let pk = keygen_pk(params, vk, &circuit).expect("Proving key generation should not fail");
+        keygen_pk_times.push(start.elapsed());
+        end_timer!(timer);
+
+        let pinning = (circuit.params(), circuit.break_points());
+        circuit.clear();
+        keygen = Some((pk, pinning));
+    }
+    let (pk, pinning) = keygen.unwrap();
+
+    let schemes = schemes
+        .iter()
+        .map(|&scheme| {
+            let mut witness_times = Vec::with_capacity(runs);
+            let mut proving_times = Vec::with_capacity(runs);
+            let mut verification_times = Vec::with_capacity(runs);
+            let mut proof_size = 0;
+            for _ in 0..runs {
+                let timer = start_timer!(|| format!("Witness generation ({scheme})"));
+                let start = Instant::now();
+                let circuit =
+                    create_circuit(CircuitBuilderStage::Prover, Some(pinning.clone()), params);
+                witness_times.push(start.elapsed());
+                end_timer!(timer);
+
+                let timer = start_timer!(|| format!("Proving ({scheme})"));
+                let start = Instant::now();
+                let snark = match scheme {
+                    MultiOpenScheme::Shplonk => {
+                        gen_snark_shplonk(params, &pk, circuit, None::<&str>)
+                    }
+                    MultiOpenScheme::Gwc => gen_snark_gwc(params, &pk, circuit, None::<&str>),
+                };
+                proving_times.push(start.elapsed());
+                end_timer!(timer);
+
+                let timer = start_timer!(|| format!("Verification ({scheme})"));
+                let start = Instant::now();
+                verify_snark(params, pk.get_vk(), &snark, scheme)
+                    .expect("Benchmarked snark should verify");
+                verification_times.push(start.elapsed());
+                end_timer!(timer);
+                proof_size = snark.proof.len();
+            }
+            SchemeBench {
+                scheme,
+                witness_generation: Timing::new(&witness_times),
+                proving: Timing::new(&proving_times),
+                verification: Timing::new(&verification_times),
+                proof_size,
+            }
+        })
+        .collect();
+
+    BenchReport {
+        name: name.to_string(),
+        k: params.k(),
+        runs,
+        keygen_vk: Timing::new(&keygen_vk_times),
+        keygen_pk: Timing::new(&keygen_pk_times),
+        pk_size: pk.to_bytes(SerdeFormat::RawBytes).len(),
+        vk_size: pk.get_vk().to_bytes(SerdeFormat::RawBytes).len(),
+        schemes,
+        peak_rss_kb: peak_rss_kb(),
+    }
+}
+
+/// Reads the peak resident set size of this process from `/proc/self/status`.
+pub fn peak_rss_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find_map(|line| line.strip_prefix("VmHWM:"))?;
+    line.trim().strip_suffix("kB")?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::Timing;
+
+    #[test]
+    fn test_timing() {
+        let timing = Timing::new(&[10, 20, 60].map(Duration::from_millis));
+        assert_eq!(timing.mean_ms, 30.0);
+        assert_eq!(timing.min_ms, 10.0);
+        assert_eq!(timing.max_ms, 60.0);
+    }
+}
@@ -1,29 +1,163 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
-#[derive(Clone, Copy, Debug, Subcommand)]
+use super::{export::InstanceFormat, pinning::MultiOpenScheme};
+
+#[derive(Clone, Debug, Subcommand)]
 pub enum SnarkCmd {
-    /// Run the mock prover
-    Mock,
+    /// Run the mock prover. Constraint failures are reported by the phase, thread and offset of the cells involved
+    Mock {
+        /// Also write the mock prover report as JSON to this path
+        #[arg(long = "json")]
+        json_path: Option<PathBuf>,
+        /// Write every cell of the witness to this path, as CSV or JSON depending on the file extension
+        #[arg(long = "dump-witness")]
+        dump_path: Option<PathBuf>,
+    },
+    /// Search for under-constrained witnesses by mutating each cell of a valid witness, together with the cells computed from it.
+    /// Reports mutations that satisfy all constraints and change a public instance. Only supports halo2-lib circuits
+    SoundnessCheck {
+        /// Also write the report as JSON to this path
+        #[arg(long = "json")]
+        json_path: Option<PathBuf>,
+    },
+    /// Report witnesses that are not constrained by anything, gate results that are never used and gates that run past the
+    /// end of their thread. Only supports halo2-lib circuits
+    Lint {
+        /// Also write the report as JSON to this path
+        #[arg(long = "json")]
+        json_path: Option<PathBuf>,
+    },
+    /// Write random valid inputs to `<DATA_PATH>/<NAME>.<i>.in`, reproducibly with `--seed`. Only supports circuit functions run with `run_with_generator`
+    GenInput {
+        /// Number of input files to write
+        #[arg(long = "count", default_value_t = 1)]
+        count: usize,
+        /// Overwrite existing input files
+        #[arg(long = "force")]
+        force: bool,
+    },
     /// Generate new proving & verifying keys
     Keygen,
+    /// Run the circuit function on the private inputs and write the witness to a file, which `prove --witness` can prove from.
+    /// Only supports halo2-lib circuits without a second phase
+    Witness {
+        /// Path of the witness file to write, defaults to `<DATA_PATH>/<NAME>.witness`
+        #[arg(long = "output")]
+        output_path: Option<PathBuf>,
+    },
     /// Generate a new proof
-    Prove,
-    /// Verify a proof
-    Verify,
+    Prove {
+        /// Prove from a witness file written by `witness`, without running the circuit function or reading the private inputs
+        #[arg(long = "witness")]
+        witness_path: Option<PathBuf>,
+    },
+    /// Verify a proof. If multiple snark files are given, they are verified together as a batch
+    Verify {
+        /// Snark files to verify, defaults to `<DATA_PATH>/<NAME>.snark`
+        snark_paths: Vec<PathBuf>,
+    },
+    /// Print the pinned circuit configuration
+    Inspect,
+    /// Time keygen, witness generation, proving and verification over several runs. Benchmarks both multi-open schemes unless `--scheme` is given
+    Bench {
+        /// Number of times to run each step
+        #[arg(long = "runs", default_value_t = 5)]
+        runs: usize,
+        /// Also write the report as JSON to this path
+        #[arg(long = "json")]
+        json_path: Option<PathBuf>,
+    },
+    /// Print statistics about the size of the circuit built from the current working tree
+    Stats {
+        /// Also write the statistics as JSON to this path
+        #[arg(long = "json")]
+        json_path: Option<PathBuf>,
+    },
+    /// Compare the circuit statistics against a baseline written by `stats --json` or built from another git revision, or the
+    /// constraint system against a baseline written by `export-cs` as JSON
+    Diff {
+        /// Baseline statistics or constraint system. Required unless `--rev` is given
+        #[arg(required_unless_present = "rev")]
+        baseline_path: Option<PathBuf>,
+        /// Git revision to build the baseline statistics from in a temporary worktree, instead of reading them from a file
+        #[arg(long = "rev", conflicts_with = "baseline_path")]
+        rev: Option<String>,
+        /// File of the same kind to compare against the baseline. Defaults to the current circuit
+        #[arg(long = "current")]
+        current_path: Option<PathBuf>,
+        /// Git revision to build the current statistics from in a temporary worktree, instead of the working tree
+        #[arg(long = "current-rev", conflicts_with = "current_path")]
+        current_rev: Option<String>,
+        /// Fail if any metric increased by more than this many percent
+        #[arg(long = "max-increase")]
+        max_increase: Option<f64>,
+    },
+    /// Export the constraint system of the circuit for review
+    ExportCs {
+        /// Path of the file to write, as JSON if it ends in `.json` and as text otherwise. Defaults to `<DATA_PATH>/<NAME>.cs.json`
+        #[arg(long = "output")]
+        output_path: Option<PathBuf>,
+    },
+    /// Render the circuit layout to a PNG or SVG. Requires the `layout` feature
+    Layout {
+        /// Path of the image to write, defaults to `<DATA_PATH>/<NAME>.layout.png`
+        #[arg(long = "output")]
+        output_path: Option<PathBuf>,
+    },
+    /// Convert a snark to JSON with a hex encoded proof
+    Export {
+        /// Path of the JSON file to write, defaults to `<DATA_PATH>/<NAME>.snark.json`
+        #[arg(long = "json")]
+        json_path: Option<PathBuf>,
+        /// Encoding of the instances
+        #[arg(long = "instance-format", value_enum, default_value_t)]
+        instance_format: InstanceFormat,
+    },
+    /// Convert a snark in JSON format back to a binary snark that can be verified
+    Import {
+        /// Path of the JSON file to read, defaults to `<DATA_PATH>/<NAME>.snark.json`
+        #[arg(long = "json")]
+        json_path: Option<PathBuf>,
+        /// Path of the snark file to write, defaults to `<DATA_PATH>/<NAME>.snark`. An existing file is never overwritten
+        #[arg(long = "output")]
+        output_path: Option<PathBuf>,
+    },
 }
 
 impl std::fmt::Display for SnarkCmd {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Mock => write!(f, "mock"),
+            Self::Mock { .. } => write!(f, "mock"),
+            Self::SoundnessCheck { .. } => write!(f, "soundness-check"),
+            Self::Lint { .. } => write!(f, "lint"),
+            Self::GenInput { .. } => write!(f, "gen-input"),
             Self::Keygen => write!(f, "keygen"),
-            Self::Prove => write!(f, "prove"),
-            Self::Verify => write!(f, "verify"),
+            Self::Witness { .. } => write!(f, "witness"),
+            Self::Prove { .. } => write!(f, "prove"),
+            Self::Verify { .. } => write!(f, "verify"),
+            Self::Inspect => write!(f, "inspect"),
+            Self::Bench { .. } => write!(f, "bench"),
+            Self::Stats { .. } => write!(f, "stats"),
+            Self::Diff { .. } => write!(f, "diff"),
+            Self::ExportCs { .. } => write!(f, "export-cs"),
+            Self::Layout { .. } => write!(f, "layout"),
+            Self::Export { .. } => write!(f, "export"),
+            Self::Import { .. } => write!(f, "import"),
         }
     }
 }
 
+impl SnarkCmd {
+    /// Whether the command runs the circuit function, and so needs the private inputs.
+    pub fn reads_inputs(&self) -> bool {
+        !matches!(
+            self,
+            Self::Prove { witness_path: Some(_) } | Self::Export { .. } | Self::Import { .. }
+        )
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 /// Command-line helper for various steps in ZK proving.
@@ -42,4 +176,15 @@ pub struct Cli {
     pub config_path: Option<PathBuf>,
     #[arg(short, long = "data-path")]
     pub data_path: Option<PathBuf>,
+    /// Multi-open scheme for proving and verifying. Defaults to the scheme pinned at keygen, which defaults to shplonk
+    #[arg(long = "scheme", value_enum)]
+    pub scheme: Option<MultiOpenScheme>,
+    /// Seed for the test SRS, the blinding factors of the prover and randomly sampled inputs, which makes them reproducible.
+    /// The seeded SRS is written to `params/kzg_bn254_<DEGREE>_seed_<SEED>.srs`, or to `$PARAMS_DIR` if that env var is set
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+    /// Run on a random input instead of the input file. Only supports circuit functions run with `run_with_generator`, which
+    /// already use a random input for `keygen` and `mock` if there is no input file
+    #[arg(long = "random-input")]
+    pub random_input: bool,
 }
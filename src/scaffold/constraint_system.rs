@@ -0,0 +1,251 @@
+//! A readable export of the `ConstraintSystem` of a circuit, so that changes to the constraints can be reviewed without reading Rust.
+use std::{fmt, fs::File, path::Path};
+
+use halo2_base::{
+    halo2_proofs::{
+        halo2curves::bn256::Fr,
+        plonk::{Any, Circuit, Column, ConstraintSystem, Expression},
+        poly::Rotation,
+    },
+    utils::fe_to_biguint,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintExport {
+    pub name: String,
+    pub degree: usize,
+    /// The polynomial expression that must vanish on every row
+    pub expression: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateExport {
+    pub name: String,
+    pub constraints: Vec<ConstraintExport>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupExport {
+    pub name: String,
+    /// The tuple of input expressions must equal a row of the tuple of table expressions
+    pub inputs: Vec<String>,
+    pub tables: Vec<String>,
+}
+
+/// The `ConstraintSystem` of a circuit, with expressions written as strings such as `q_0 * (advice[0] + advice[0](+1) * advice[0](+2) - advice[0](+3))`.
+///
+/// Columns are written as `advice[i]`, `fixed[i]`, `instance[i]`, selectors as `q_i` and challenges as `challenge[i]`,
+/// followed by the rotation in parentheses if it is not the current row.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintSystemExport {
+    /// Phase of each advice column
+    pub advice_column_phases: Vec<u8>,
+    pub num_fixed_columns: usize,
+    pub num_instance_columns: usize,
+    pub num_selectors: usize,
+    /// Phase after which each challenge is available
+    pub challenge_phases: Vec<u8>,
+    pub gates: Vec<GateExport>,
+    pub lookups: Vec<LookupExport>,
+    /// Columns with equality constraints enabled
+    pub permutation_columns: Vec<String>,
+    pub max_degree: usize,
+    pub blinding_factors: usize,
+    pub minimum_rows: usize,
+}
+
+impl ConstraintSystemExport {
+    pub fn new<C: Circuit<Fr>>(params: C::Params) -> Self {
+        let mut cs = ConstraintSystem::default();
+        C::configure_with_params(&mut cs, params);
+        let gates = cs
+            .gates()
+            .iter()
+            .map(|gate| GateExport {
+                name: gate.name().to_string(),
+                constraints: gate
+                    .polynomials()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, poly)| ConstraintExport {
+                        name: gate.constraint_name(i).to_string(),
+                        degree: poly.degree(),
+                        expression: fmt_expression(poly),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let lookups = cs
+            .lookups()
+            .iter()
+            .map(|lookup| LookupExport {
+                name: lookup.name().to_string(),
+                inputs: lookup.input_expressions().iter().map(fmt_expression).collect(),
+                tables: lookup.table_expressions().iter().map(fmt_expression).collect(),
+            })
+            .collect();
+        let permutation_columns = cs.permutation().get_columns().iter().map(fmt_column).collect();
+        Self {
+            advice_column_phases: cs.advice_column_phase(),
+            num_fixed_columns: cs.num_fixed_columns(),
+            num_instance_columns: cs.num_instance_columns(),
+            num_selectors: cs.num_selectors(),
+            challenge_phases: cs.challenge_phase(),
+            gates,
+            lookups,
+            permutation_columns,
+            max_degree: cs.degree(),
+            blinding_factors: cs.blinding_factors(),
+            minimum_rows: cs.minimum_rows(),
+        }
+    }
+
+    /// Reads a constraint system written as JSON by [Self::write].
+    pub fn read(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let file = File::open(path).unwrap_or_else(|_| panic!("Could not read file at {path:?}"));
+        serde_json::from_reader(file).expect("Could not read constraint system")
+    }
+
+    /// The cost metrics that are compared by [super::stats::StatsDiff].
+    pub fn metrics(&self) -> Vec<(String, usize)> {
+        vec![
+            ("advice columns".to_string(), self.advice_column_phases.len()),
+            ("fixed columns".to_string(), self.num_fixed_columns),
+            ("instance columns".to_string(), self.num_instance_columns),
+            ("selectors".to_string(), self.num_selectors),
+            ("gates".to_string(), self.gates.len()),
+            ("constraints".to_string(), self.gates.iter().map(|gate| gate.constraints.len()).sum()),
+            ("lookup arguments".to_string(), self.lookups.len()),
+            ("permutation columns".to_string(), self.permutation_columns.len()),
+            ("max gate degree".to_string(), self.max_degree),
+            ("blinding factors".to_string(), self.blinding_factors),
+        ]
+    }
+
+    /// Writes JSON if `path` ends in `.json` and the text form otherwise.
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let mut file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+            serde_json::to_writer_pretty(file, self).expect("Could not write constraint system");
+        } else {
+            use std::io::Write;
+            write!(file, "{self}").expect("Could not write constraint system");
+        }
+    }
+}
+
+impl fmt::Display for ConstraintSystemExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Columns:")?;
+        for (i, phase) in self.advice_column_phases.iter().enumerate() {
+            writeln!(f, "  advice[{i}]: phase {phase}")?;
+        }
+        writeln!(f, "  fixed columns: {}", self.num_fixed_columns)?;
+        writeln!(f, "  instance columns: {}", self.num_instance_columns)?;
+        writeln!(f, "  selectors: {}", self.num_selectors)?;
+        for (i, phase) in self.challenge_phases.iter().enumerate() {
+            writeln!(f, "  challenge[{i}]: after phase {phase}")?;
+        }
+        writeln!(f, "Gates:")?;
+        for gate in &self.gates {
+            writeln!(f, "  {}:", gate.name)?;
+            for constraint in &gate.constraints {
+                let name = if constraint.name.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}: ", constraint.name)
+                };
+                writeln!(
+                    f,
+                    "    {name}{} = 0 (degree {})",
+                    constraint.expression, constraint.degree
+                )?;
+            }
+        }
+        writeln!(f, "Lookups:")?;
+        for lookup in &self.lookups {
+            writeln!(
+                f,
+                "  {}: ({}) in ({})",
+                lookup.name,
+                lookup.inputs.join(", "),
+                lookup.tables.join(", ")
+            )?;
+        }
+        writeln!(f, "Permutation columns: {}", self.permutation_columns.join(", "))?;
+        writeln!(f, "Max degree: {}", self.max_degree)?;
+        writeln!(f, "Blinding factors: {}", self.blinding_factors)?;
+        writeln!(f, "Minimum rows: {}", self.minimum_rows)
+    }
+}
+
+fn fmt_rotation(rotation: Rotation) -> String {
+    match rotation.0 {
+        0 => String::new(),
+        r => format!("({r:+})"),
+    }
+}
+
+fn fmt_column(column: &Column<Any>) -> String {
+    match column.column_type() {
+        Any::Advice(_) => format!("advice[{}]", column.index()),
+        Any::Fixed => format!("fixed[{}]", column.index()),
+        Any::Instance => format!("instance[{}]", column.index()),
+    }
+}
+
+/// Small constants are written in decimal, possibly negated, and other constants in hex.
+fn fmt_constant(c: Fr) -> String {
+    let [value, negated] = [c, -c].map(|x| fe_to_biguint(&x));
+    if value.bits() <= 64 {
+        value.to_string()
+    } else if negated.bits() <= 64 {
+        format!("-{negated}")
+    } else {
+        format!("{c:?}")
+    }
+}
+
+pub fn fmt_expression(expression: &Expression<Fr>) -> String {
+    expression.evaluate(
+        &fmt_constant,
+        &|selector| format!("q_{}", selector.index()),
+        &|query| format!("fixed[{}]{}", query.column_index(), fmt_rotation(query.rotation())),
+        &|query| format!("advice[{}]{}", query.column_index(), fmt_rotation(query.rotation())),
+        &|query| format!("instance[{}]{}", query.column_index(), fmt_rotation(query.rotation())),
+        &|challenge| format!("challenge[{}]", challenge.index()),
+        &|a| format!("-{a}"),
+        &|a, b| match b.strip_prefix('-') {
+            Some(b) => format!("({a} - {b})"),
+            None => format!("({a} + {b})"),
+        },
+        &|a, b| format!("{a} * {b}"),
+        &|a, c| format!("{a} * {}", fmt_constant(c)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vanilla_circuits::standard_plonk::StandardPlonk;
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::ConstraintSystemExport;
+
+    #[test]
+    fn test_export_standard_plonk() {
+        let cs = ConstraintSystemExport::new::<StandardPlonk<Fr>>(());
+        assert_eq!(cs.advice_column_phases, vec![0; 3]);
+        assert_eq!(cs.num_fixed_columns, 5);
+        assert_eq!(cs.num_instance_columns, 0);
+        assert_eq!(cs.gates.len(), 1);
+        assert_eq!(
+            cs.gates[0].constraints[0].expression,
+            "((((fixed[0] * advice[0] + fixed[1] * advice[1]) + fixed[2] * advice[2]) + fixed[3] * advice[0] * advice[1]) + fixed[4])"
+        );
+        assert_eq!(cs.permutation_columns.len(), 3);
+    }
+}
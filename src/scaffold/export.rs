@@ -0,0 +1,208 @@
+//! Conversion of snarks to and from a JSON representation that can be passed through web APIs.
+//!
+//! The proof transcript is encoded as a single `0x`-prefixed hex string and each instance is encoded as a string,
+//! either as `0x`-prefixed big-endian hex or as a decimal integer. Both encodings are accepted when importing.
+use std::{fs::File, path::Path};
+
+use clap::ValueEnum;
+use halo2_base::{
+    halo2_proofs::{
+        halo2curves::{
+            bn256::{Bn256, Fr, G1Affine},
+            ff::PrimeField,
+        },
+        plonk::VerifyingKey,
+        poly::kzg::commitment::ParamsKZG,
+    },
+    utils::fe_to_biguint,
+};
+use serde::{Deserialize, Serialize};
+use snark_verifier_sdk::{
+    snark_verifier::system::halo2::{compile, Config},
+    Snark,
+};
+
+/// How field elements in `instances` are written when exporting a snark.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum InstanceFormat {
+    /// `0x`-prefixed big-endian hex
+    #[default]
+    Hex,
+    /// Decimal integer
+    Decimal,
+}
+
+/// JSON representation of a [Snark], without the protocol. The protocol is recomputed from the verifying key on import.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnarkJson {
+    pub proof: String,
+    pub instances: Vec<Vec<String>>,
+}
+
+impl SnarkJson {
+    pub fn from_snark(snark: &Snark, format: InstanceFormat) -> Self {
+        let proof = format!("0x{}", hex::encode(&snark.proof));
+        let instances = snark
+            .instances
+            .iter()
+            .map(|column| column.iter().map(|x| encode_fr(x, format)).collect())
+            .collect();
+        Self { proof, instances }
+    }
+
+    /// Decodes the proof bytes and the instances, in the same layout as [Snark].
+    pub fn decode(&self) -> Result<(Vec<u8>, Vec<Vec<Fr>>), String> {
+        let proof = hex::decode(strip_hex_prefix(&self.proof))
+            .map_err(|e| format!("proof is not a valid hex string: {e}"))?;
+        let instances = self
+            .instances
+            .iter()
+            .map(|column| column.iter().map(|x| decode_fr(x)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((proof, instances))
+    }
+
+    /// Reconstructs the full [Snark] by recompiling the protocol from the verifying key.
+    pub fn to_snark(
+        &self,
+        params: &ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+    ) -> Result<Snark, String> {
+        let (proof, instances) = self.decode()?;
+        let num_instance = instances.iter().map(Vec::len).collect();
+        let protocol = compile(params, vk, Config::kzg().with_num_instance(num_instance));
+        Ok(Snark::new(protocol, instances, proof))
+    }
+}
+
+pub fn encode_fr(x: &Fr, format: InstanceFormat) -> String {
+    match format {
+        InstanceFormat::Hex => {
+            let mut bytes = x.to_bytes();
+            bytes.reverse();
+            format!("0x{}", hex::encode(bytes))
+        }
+        InstanceFormat::Decimal => fe_to_biguint(x).to_string(),
+    }
+}
+
+/// Parses a field element from either a `0x`-prefixed big-endian hex string or a decimal string.
+///
+/// Only the canonical encodings written by [encode_fr] are accepted: hex must be `0x` followed by exactly 64 lowercase digits,
+/// decimal must not have leading zeros, and in both the value must be less than the field modulus.
+pub fn decode_fr(s: &str) -> Result<Fr, String> {
+    if let Some(hex_str) = s.strip_prefix("0x") {
+        if hex_str.len() != 64 || hex_str.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Err(format!("hex field element {s} is not 0x followed by 64 lowercase digits"));
+        }
+        let bytes =
+            hex::decode(hex_str).map_err(|e| format!("invalid hex field element {s}: {e}"))?;
+        let mut repr = [0u8; 32];
+        for (dst, src) in repr.iter_mut().zip(bytes.iter().rev()) {
+            *dst = *src;
+        }
+        Option::from(Fr::from_bytes(&repr))
+            .ok_or_else(|| format!("hex field element {s} is not less than the field modulus"))
+    } else {
+        // `from_str_vartime` reduces values modulo the field modulus, so we check that the value is canonical by encoding it again
+        let x =
+            Fr::from_str_vartime(s).ok_or_else(|| format!("invalid decimal field element {s}"))?;
+        if fe_to_biguint(&x).to_string() != s {
+            return Err(format!("decimal field element {s} is not less than the field modulus"));
+        }
+        Ok(x)
+    }
+}
+
+fn strip_hex_prefix(s: &str) -> &str {
+    s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s)
+}
+
+pub fn read_snark_json(path: impl AsRef<Path>) -> Result<SnarkJson, String> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| format!("Could not read file at {path:?}: {e:?}"))?;
+    serde_json::from_reader(file).map_err(|e| format!("{path:?} is not a valid snark JSON: {e}"))
+}
+
+pub fn write_snark_json(path: impl AsRef<Path>, json: &SnarkJson) {
+    let path = path.as_ref();
+    let file = File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+    serde_json::to_writer_pretty(file, json).expect("Could not write snark JSON");
+}
+
+/// Writes a [Snark] in the same bincode format as `gen_snark_shplonk`, so it can be read with `read_snark`.
+pub fn write_snark(path: impl AsRef<Path>, snark: &Snark) {
+    let path = path.as_ref();
+    let file = File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+    bincode::serialize_into(file, snark).expect("Could not write snark");
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::halo2_proofs::{arithmetic::Field, halo2curves::bn256::Fr};
+    use rand::rngs::OsRng;
+
+    use super::{decode_fr, encode_fr, InstanceFormat, SnarkJson};
+
+    #[test]
+    fn test_fr_round_trip() {
+        for x in [Fr::ZERO, Fr::ONE, -Fr::ONE, Fr::random(OsRng)] {
+            for format in [InstanceFormat::Hex, InstanceFormat::Decimal] {
+                assert_eq!(decode_fr(&encode_fr(&x, format)).unwrap(), x);
+            }
+        }
+        assert_eq!(
+            decode_fr("0x0000000000000000000000000000000000000000000000000000000000000048")
+                .unwrap(),
+            Fr::from(72)
+        );
+        assert_eq!(decode_fr("72").unwrap(), Fr::from(72));
+        assert!(decode_fr("0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001")
+            .is_err());
+    }
+
+    #[test]
+    fn test_hex_not_canonical() {
+        let x = encode_fr(&Fr::from(72), InstanceFormat::Hex);
+        assert!(decode_fr(&x).is_ok());
+        // without leading zeros, with an extra leading zero, with an upper case prefix or digits, and without the prefix
+        let encodings: [&str; 5] =
+            ["0x48", "0x048", &format!("0x0{}", &x[2..]), &x.replace("0x", "0X"), &x[2..]];
+        for s in encodings {
+            assert!(decode_fr(s).is_err(), "{s} should be rejected");
+        }
+        let x = encode_fr(&-Fr::ONE, InstanceFormat::Hex);
+        assert!(decode_fr(&x.to_uppercase().replace("0X", "0x")).is_err());
+    }
+
+    #[test]
+    fn test_decimal_not_canonical() {
+        let modulus =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        assert!(decode_fr(modulus).is_err());
+        // the modulus plus 72 would otherwise import as 72
+        assert!(decode_fr(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495689"
+        )
+        .is_err());
+        assert_eq!(
+            decode_fr(
+                "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+            )
+            .unwrap(),
+            -Fr::ONE
+        );
+        assert!(decode_fr("072").is_err());
+    }
+
+    #[test]
+    fn test_snark_json_decode() {
+        let json = SnarkJson {
+            proof: "0x00ff10".to_string(),
+            instances: vec![vec![encode_fr(&Fr::ONE, InstanceFormat::Hex), "2".to_string()]],
+        };
+        let (proof, instances) = json.decode().unwrap();
+        assert_eq!(proof, vec![0x00, 0xff, 0x10]);
+        assert_eq!(instances, vec![vec![Fr::ONE, Fr::from(2)]]);
+    }
+}
@@ -0,0 +1,97 @@
+//! Random private inputs for circuit functions, shared by the `gen-input` command, keygen, fuzzing and benchmarks.
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use halo2_base::halo2_proofs::halo2curves::{bn256::Fr, ff::Field};
+use rand::RngCore;
+use serde::Serialize;
+
+use super::seeded_rng;
+
+/// Private inputs of a circuit function that can be sampled at random.
+///
+/// Every generated input should be valid, i.e. the circuit should be satisfied on it.
+pub trait GenerateInput: Serialize + Sized {
+    fn generate(rng: &mut impl RngCore) -> Self;
+}
+
+impl GenerateInput for Fr {
+    fn generate(rng: &mut impl RngCore) -> Self {
+        Fr::random(rng)
+    }
+}
+
+/// Samples `count` inputs from [seeded_rng], so they are reproducible if a `seed` is given.
+pub fn generate_inputs<T: GenerateInput>(count: usize, seed: Option<u64>) -> Vec<T> {
+    let mut rng = seeded_rng(seed);
+    (0..count).map(|_| T::generate(&mut rng)).collect()
+}
+
+/// Writes each of `inputs` as JSON to `<dir>/<name>.<i>.in`, so they can be passed to the CLI with `--input <name>.<i>.in`.
+///
+/// Unless `overwrite` is set, panics before writing anything if any of the files already exists.
+/// Returns the paths of the files written.
+pub fn write_inputs<T: Serialize>(
+    dir: impl AsRef<Path>,
+    name: &str,
+    inputs: &[T],
+    overwrite: bool,
+) -> Vec<PathBuf> {
+    let dir = dir.as_ref();
+    let paths: Vec<_> = (0..inputs.len()).map(|i| dir.join(format!("{name}.{i}.in"))).collect();
+    if !overwrite {
+        for path in &paths {
+            assert!(
+                !path.exists(),
+                "Input file already exists at {path:?}, remove it or pass --force to overwrite it"
+            );
+        }
+    }
+    fs::create_dir_all(dir).unwrap();
+    inputs
+        .iter()
+        .zip(paths)
+        .map(|(input, path)| {
+            let file =
+                File::create(&path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+            serde_json::to_writer_pretty(file, input).expect("Could not write input");
+            path
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::{generate_inputs, write_inputs};
+
+    #[test]
+    fn test_generate_inputs_seeded() {
+        let inputs = generate_inputs::<Fr>(3, Some(7));
+        assert_eq!(inputs, generate_inputs::<Fr>(3, Some(7)));
+        assert_ne!(inputs, generate_inputs::<Fr>(3, Some(8)));
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths = write_inputs(dir.path(), "test", &inputs, false);
+        assert_eq!(paths[2], dir.path().join("test.2.in"));
+        let read: Fr = serde_json::from_reader(std::fs::File::open(&paths[2]).unwrap()).unwrap();
+        assert_eq!(read, inputs[2]);
+    }
+
+    #[test]
+    fn test_write_inputs_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let inputs = generate_inputs::<Fr>(2, Some(7));
+        write_inputs(dir.path(), "test", &inputs[..1], false);
+        let result = std::panic::catch_unwind(|| write_inputs(dir.path(), "test", &inputs, false));
+        assert!(result.is_err(), "existing input files should not be overwritten");
+        assert!(!dir.path().join("test.1.in").exists(), "nothing should be written on failure");
+
+        let paths = write_inputs(dir.path(), "test", &inputs, true);
+        let read: Fr = serde_json::from_reader(std::fs::File::open(&paths[1]).unwrap()).unwrap();
+        assert_eq!(read, inputs[1]);
+    }
+}
@@ -0,0 +1,47 @@
+//! Renders the region and column layout of a circuit using the `CircuitLayout` from halo2's `dev-graph` feature.
+//! This requires compiling with `--features layout`.
+use std::path::Path;
+
+use halo2_base::halo2_proofs::{halo2curves::bn256::Fr, plonk::Circuit};
+
+/// Renders the layout of `circuit` with `2^k` rows to `path` as a PNG or SVG, depending on the file extension.
+///
+/// Columns are ordered as instance, advice and then fixed columns. Selectors are shown as fixed columns, and cells
+/// with equality constraints are marked, with lines between the cells of each copy constraint.
+#[cfg(feature = "layout")]
+pub fn render_layout<C: Circuit<Fr>>(k: u32, circuit: &C, path: &Path) {
+    use plotters::prelude::{BitMapBackend, IntoDrawingArea, SVGBackend};
+
+    const SIZE: (u32, u32) = (1024, 3072);
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("png") => draw(k, circuit, BitMapBackend::new(path, SIZE).into_drawing_area()),
+        Some("svg") => draw(k, circuit, SVGBackend::new(path, SIZE).into_drawing_area()),
+        _ => panic!("Layout path {path:?} should end in .png or .svg"),
+    }
+}
+
+#[cfg(feature = "layout")]
+fn draw<C, DB>(
+    k: u32,
+    circuit: &C,
+    root: plotters::drawing::DrawingArea<DB, plotters::coord::Shift>,
+) where
+    C: Circuit<Fr>,
+    DB: plotters::prelude::DrawingBackend,
+{
+    use halo2_base::halo2_proofs::dev::CircuitLayout;
+    use plotters::style::WHITE;
+
+    root.fill(&WHITE).expect("Could not draw layout background");
+    CircuitLayout::default()
+        .mark_equality_cells(true)
+        .show_equality_constraints(true)
+        .render(k, circuit, &root)
+        .expect("Could not render circuit layout");
+    root.present().expect("Could not write circuit layout");
+}
+
+#[cfg(not(feature = "layout"))]
+pub fn render_layout<C: Circuit<Fr>>(_k: u32, _circuit: &C, _path: &Path) {
+    panic!("Rendering the circuit layout requires the `layout` feature, run with `cargo run --features layout`");
+}
@@ -0,0 +1,170 @@
+//! Static checks on the virtual trace of a `halo2-lib` circuit for cells that no constraint depends on.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    path::Path,
+};
+
+use serde::Serialize;
+
+use super::{
+    export::{encode_fr, InstanceFormat},
+    trace::{CellLocation, VirtualTrace},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LintWarning {
+    /// A witness that is not part of any enabled gate, copy constraint, lookup or instance, so a prover can set it to anything.
+    /// Usually a value from `ctx.load_witness` that was never used.
+    DanglingWitness { cell: CellLocation, value: String },
+    /// The output `d` of a basic gate `a + b * c = d` that is never used afterwards, which wastes cells
+    UnusedResult { cell: CellLocation, value: String },
+    /// A basic gate enabled so close to the end of its thread that it touches cells that were never assigned in the thread.
+    /// In the final circuit those cells hold whatever is assigned next in the same column.
+    GatePastEnd { cell: CellLocation, thread_len: usize },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DanglingWitness { cell, value } => {
+                write!(f, "witness {value} at {cell} is not constrained by anything")
+            }
+            Self::UnusedResult { cell, value } => {
+                write!(f, "result {value} of the gate ending at {cell} is never used")
+            }
+            Self::GatePastEnd { cell, thread_len } => write!(
+                f,
+                "gate enabled at {cell} uses cells past the end of the thread, which has {thread_len} cells"
+            ),
+        }
+    }
+}
+
+/// The result of the `lint` command.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LintReport {
+    pub warnings: Vec<LintWarning>,
+}
+
+impl LintReport {
+    /// Lints every thread of the virtual trace, reporting warnings in the order of the cells they refer to.
+    pub fn new(trace: &VirtualTrace) -> Self {
+        let builder = trace.builder();
+        // cells that are constrained by something other than basic gates
+        let mut constrained = HashSet::new();
+        {
+            let copy_manager = builder.core().copy_manager.lock().unwrap();
+            for (left, right) in &copy_manager.advice_equalities {
+                constrained.extend([left, right].into_iter().filter_map(|c| trace.location(*c)));
+            }
+            for (_, cell) in &copy_manager.constant_equalities {
+                constrained.extend(trace.location(*cell));
+            }
+        }
+        for lookup_manager in builder.lookup_manager() {
+            for [cell] in lookup_manager.cells_to_lookup.lock().unwrap().iter() {
+                constrained.extend(cell.cell.and_then(|cell| trace.location(cell)));
+            }
+        }
+        for cell in builder.assigned_instances.iter().flatten() {
+            constrained.extend(cell.cell.and_then(|cell| trace.location(cell)));
+        }
+
+        let mut warnings = vec![];
+        for ctx in trace.threads() {
+            let location = |offset| CellLocation { phase: ctx.phase(), thread: ctx.id(), offset };
+            let len = ctx.advice.len();
+            // number of enabled gates each cell is part of
+            let mut gates = HashMap::<usize, usize>::new();
+            let mut results = HashSet::new();
+            let mut past_end = HashSet::new();
+            for (offset, _) in ctx.selector.iter().enumerate().filter(|(_, enabled)| **enabled) {
+                if offset + 4 > len {
+                    past_end.insert(offset);
+                }
+                for i in offset..len.min(offset + 4) {
+                    *gates.entry(i).or_default() += 1;
+                }
+                results.insert(offset + 3);
+            }
+            for (offset, value) in ctx.advice.iter().enumerate() {
+                if past_end.contains(&offset) {
+                    warnings
+                        .push(LintWarning::GatePastEnd { cell: location(offset), thread_len: len });
+                }
+                if constrained.contains(&location(offset)) {
+                    continue;
+                }
+                let value = encode_fr(&value.evaluate(), InstanceFormat::Hex);
+                match gates.get(&offset) {
+                    None => warnings
+                        .push(LintWarning::DanglingWitness { cell: location(offset), value }),
+                    Some(1) if results.contains(&offset) => {
+                        warnings.push(LintWarning::UnusedResult { cell: location(offset), value })
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Self { warnings }
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        serde_json::to_writer_pretty(file, self).expect("Could not write lint report");
+    }
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} warnings", self.warnings.len())?;
+        for warning in &self.warnings {
+            write!(f, "\n  {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::{
+        gates::{
+            circuit::{builder::BaseCircuitBuilder, CircuitBuilderStage},
+            GateChip, GateInstructions,
+        },
+        halo2_proofs::halo2curves::bn256::Fr,
+        QuantumCell::Witness,
+    };
+
+    use super::{LintReport, LintWarning};
+    use crate::scaffold::trace::{CellLocation, VirtualTrace};
+
+    #[test]
+    fn test_lint() {
+        let mut builder = BaseCircuitBuilder::<Fr>::from_stage(CircuitBuilderStage::Mock);
+        let ctx = builder.main(0);
+        let gate = GateChip::default();
+        let [x, _unused] = [1, 2].map(|v| ctx.load_witness(Fr::from(v)));
+        let _sum = gate.add(ctx, x, x);
+        ctx.assign_region([Witness(Fr::from(3))], [0]);
+
+        let cell = |offset| CellLocation { phase: 0, thread: 0, offset };
+        let warnings = LintReport::new(&VirtualTrace::new(&builder)).warnings;
+        let kinds = warnings
+            .iter()
+            .map(|warning| match warning {
+                LintWarning::DanglingWitness { cell, .. } => ("dangling", *cell),
+                LintWarning::UnusedResult { cell, .. } => ("unused", *cell),
+                LintWarning::GatePastEnd { cell, .. } => ("past end", *cell),
+            })
+            .collect::<Vec<_>>();
+        // x, _unused, then `x + x * 1 = sum` at offsets 2..6, then the gate at offset 6 in a thread of length 7
+        assert_eq!(kinds, [("dangling", cell(1)), ("unused", cell(5)), ("past end", cell(6))]);
+        builder.clear();
+    }
+}
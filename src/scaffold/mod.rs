@@ -5,70 +5,359 @@
 use halo2_base::{
     gates::{
         circuit::{builder::BaseCircuitBuilder, BaseCircuitParams, CircuitBuilderStage},
-        flex_gate::MultiPhaseThreadBreakPoints,
+        flex_gate::{threads::parallelize_core, MultiPhaseThreadBreakPoints},
     },
     halo2_proofs::{
         dev::MockProver,
-        halo2curves::bn256::{Bn256, Fr, G1Affine},
-        plonk::{verify_proof, Circuit, ProvingKey, VerifyingKey},
-        poly::{
-            commitment::{Params, ParamsProver},
-            kzg::{
-                commitment::{KZGCommitmentScheme, ParamsKZG},
-                multiopen::VerifierSHPLONK,
-                strategy::SingleStrategy,
-            },
-        },
-        SerdeFormat,
+        halo2curves::bn256::{Bn256, Fr},
+        plonk::{keygen_vk, Circuit},
+        poly::{commitment::Params, kzg::commitment::ParamsKZG},
     },
-    utils::fs::gen_srs,
-    AssignedValue,
-};
-use serde::de::DeserializeOwned;
-use snark_verifier_sdk::{
-    gen_pk,
-    halo2::{gen_snark_shplonk, read_snark, PoseidonTranscript},
-    read_pk, CircuitExt, NativeLoader,
+    utils::{fs::gen_srs, ScalarField},
+    AssignedValue, Context,
 };
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{de::DeserializeOwned, Serialize};
+use snark_verifier_sdk::{halo2::read_snark, CircuitExt};
 use std::{
+    cell::RefCell,
     env::var,
+    fmt::Debug,
     fs::{self, File},
     io::{BufReader, BufWriter},
-    path::{Path, PathBuf},
+    marker::PhantomData,
+    path::PathBuf,
     time::Instant,
 };
 
-use self::cmd::{Cli, SnarkCmd};
+use self::{
+    bench::bench,
+    cmd::{Cli, SnarkCmd},
+    constraint_system::ConstraintSystemExport,
+    export::{read_snark_json, write_snark, write_snark_json, SnarkJson},
+    input::{generate_inputs, write_inputs, GenerateInput},
+    layout::render_layout,
+    lint::LintReport,
+    pinning::{CircuitPinning, MultiOpenScheme},
+    prove::Prover,
+    revision::stats_at_revision,
+    soundness::soundness_check,
+    stats::{CircuitStats, DiffInput, StatsDiff},
+    trace::{MockReport, VirtualTrace},
+    verify::{BatchVerifyError, Verifier},
+    witness::WitnessFile,
+};
 
+pub mod bench;
 pub mod cmd;
+pub mod constraint_system;
+pub mod export;
+pub mod input;
+pub mod layout;
+pub mod lint;
+pub mod pinning;
+pub mod prove;
+pub mod revision;
+pub mod rlc;
+pub mod soundness;
+pub mod stats;
+pub mod testing;
+pub mod trace;
+pub mod vanilla;
+pub mod verify;
+pub mod witness;
 
-pub struct CircuitScaffold<T, Fn> {
+pub struct CircuitScaffold<T, I, Fn> {
     f: Fn,
     private_inputs: T,
+    _instances: PhantomData<I>,
 }
 
-pub fn run<T: DeserializeOwned>(
-    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
-    cli: Cli,
-) {
+/// The public instances that a circuit function populates, one `Vec` per instance column.
+///
+/// A function taking `&mut Vec<AssignedValue<Fr>>` creates a circuit with a single instance column, while a function taking
+/// `&mut [Vec<AssignedValue<Fr>>; N]` creates a circuit with `N` instance columns.
+pub trait PublicInstances {
+    const NUM_COLUMNS: usize;
+
+    fn new() -> Self;
+
+    fn into_columns(self) -> Vec<Vec<AssignedValue<Fr>>>;
+}
+
+impl PublicInstances for Vec<AssignedValue<Fr>> {
+    const NUM_COLUMNS: usize = 1;
+
+    fn new() -> Self {
+        vec![]
+    }
+
+    fn into_columns(self) -> Vec<Vec<AssignedValue<Fr>>> {
+        vec![self]
+    }
+}
+
+impl<const N: usize> PublicInstances for [Vec<AssignedValue<Fr>>; N] {
+    const NUM_COLUMNS: usize = N;
+
+    fn new() -> Self {
+        std::array::from_fn(|_| vec![])
+    }
+
+    fn into_columns(self) -> Vec<Vec<AssignedValue<Fr>>> {
+        self.into()
+    }
+}
+
+/// A circuit that the scaffold can take through every [SnarkCmd].
+pub trait ScaffoldCircuit: Circuit<Fr> + CircuitExt<Fr> {
+    /// The break points to pin at keygen. Only valid after the circuit has been synthesized.
+    fn break_points(&self) -> MultiPhaseThreadBreakPoints;
+
+    /// Clears the execution trace once the circuit is no longer needed.
+    fn clear(&mut self);
+
+    /// Calls `f` on the virtual trace of the circuit, if it was built with `halo2-lib`. Only valid after the circuit has been synthesized.
+    fn with_virtual_trace<R>(&self, _f: impl FnOnce(&VirtualTrace) -> R) -> Option<R> {
+        None
+    }
+
+    /// The witness of the circuit for `prove --witness`, if the circuit supports witness files. Only valid after witness generation.
+    fn witness(&self) -> Option<WitnessFile> {
+        None
+    }
+
+    /// Creates the circuit for proving from a witness file, without running the circuit function.
+    fn from_witness(
+        _params: Self::Params,
+        _break_points: MultiPhaseThreadBreakPoints,
+        _witness: WitnessFile,
+    ) -> Option<Self> {
+        None
+    }
+}
+
+impl ScaffoldCircuit for BaseCircuitBuilder<Fr> {
+    fn break_points(&self) -> MultiPhaseThreadBreakPoints {
+        BaseCircuitBuilder::break_points(self)
+    }
+
+    fn clear(&mut self) {
+        BaseCircuitBuilder::clear(self)
+    }
+
+    fn with_virtual_trace<R>(&self, f: impl FnOnce(&VirtualTrace) -> R) -> Option<R> {
+        Some(f(&VirtualTrace::new(self)))
+    }
+
+    fn witness(&self) -> Option<WitnessFile> {
+        WitnessFile::new(self)
+    }
+
+    fn from_witness(
+        params: BaseCircuitParams,
+        break_points: MultiPhaseThreadBreakPoints,
+        witness: WitnessFile,
+    ) -> Option<Self> {
+        Some(witness.into_builder(params, break_points))
+    }
+}
+
+/// Directory of the input files and keys, which is `--data-path` or `data` by default.
+fn data_path(cli: &Cli) -> PathBuf {
+    cli.data_path.clone().unwrap_or_else(|| PathBuf::from("data"))
+}
+
+/// Path of the input file specified by `cli`, which is located in the data directory.
+fn input_path(cli: &Cli) -> PathBuf {
     let name = &cli.name;
-    let input_path = PathBuf::from("data")
-        .join(cli.input_path.clone().unwrap_or_else(|| PathBuf::from(format!("{name}.in"))));
-    let private_inputs: T = serde_json::from_reader(
+    data_path(cli)
+        .join(cli.input_path.clone().unwrap_or_else(|| PathBuf::from(format!("{name}.in"))))
+}
+
+/// Reads the private inputs from the input file specified by `cli`, which is located in the data directory.
+pub fn read_inputs<T: DeserializeOwned>(cli: &Cli) -> T {
+    assert!(
+        !cli.random_input,
+        "--random-input needs a circuit function whose inputs implement `GenerateInput`, run with `run_with_generator`"
+    );
+    let input_path = input_path(cli);
+    serde_json::from_reader(
         File::open(&input_path)
             .unwrap_or_else(|e| panic!("Input file not found at {input_path:?}. {e:?}")),
     )
-    .expect("Input file should be a valid JSON file");
+    .expect("Input file should be a valid JSON file")
+}
+
+pub fn run<T: DeserializeOwned, I: PublicInstances>(
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    cli: Cli,
+) {
+    if !cli.command.reads_inputs() {
+        return run_without_inputs::<BaseCircuitBuilder<Fr>>(cli);
+    }
+    let private_inputs = read_inputs(&cli);
     run_on_inputs(f, cli, private_inputs)
 }
 
-pub fn run_on_inputs<T: DeserializeOwned>(
-    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
+/// Like [run], but runs the circuit function as many times as the command needs, which the `bench` command requires.
+pub fn run_repeatable<T: DeserializeOwned + Clone, I: PublicInstances>(
+    f: impl Fn(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    cli: Cli,
+) {
+    if !cli.command.reads_inputs() {
+        return run_without_inputs::<BaseCircuitBuilder<Fr>>(cli);
+    }
+    let private_inputs = read_inputs(&cli);
+    run_repeatable_on_inputs(f, cli, private_inputs)
+}
+
+/// Like [run_repeatable], but for circuit functions whose inputs can be sampled at random, which also supports the `gen-input` command.
+///
+/// Commands read the input file like [run]. With `--random-input` they run on a random input instead, and so do `keygen` and
+/// `mock` if there is no input file, since they only need some valid input.
+pub fn run_with_generator<T: GenerateInput + DeserializeOwned + Clone, I: PublicInstances>(
+    f: impl Fn(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    cli: Cli,
+) {
+    if let SnarkCmd::GenInput { count, force } = cli.command {
+        let inputs = generate_inputs::<T>(count, cli.seed);
+        for path in write_inputs(data_path(&cli), &cli.name, &inputs, force) {
+            println!("Input written to: {path:?}");
+        }
+        return;
+    }
+    if !cli.command.reads_inputs() {
+        return run_without_inputs::<BaseCircuitBuilder<Fr>>(cli);
+    }
+    let any_input = matches!(cli.command, SnarkCmd::Keygen | SnarkCmd::Mock { .. });
+    let private_inputs = if cli.random_input {
+        println!("Using a random input");
+        generate_inputs(1, cli.seed).remove(0)
+    } else if any_input && !input_path(&cli).exists() {
+        println!("No input file at {:?}, using a random input", input_path(&cli));
+        generate_inputs(1, cli.seed).remove(0)
+    } else {
+        read_inputs(&cli)
+    };
+    run_repeatable_on_inputs(f, cli, private_inputs)
+}
+
+pub fn run_on_inputs<T: DeserializeOwned, I: PublicInstances>(
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    cli: Cli,
+    private_inputs: T,
+) {
+    run_cli::<BaseCircuitBuilder<Fr>>(
+        call_once(&cli.command, |stage, pinning, params| {
+            create_circuit(f, private_inputs, stage, pinning, params)
+        }),
+        cli,
+    )
+}
+
+pub fn run_repeatable_on_inputs<T: Clone, I: PublicInstances>(
+    f: impl Fn(&mut BaseCircuitBuilder<Fr>, T, &mut I),
     cli: Cli,
     private_inputs: T,
 ) {
-    let precircuit = CircuitScaffold { f, private_inputs };
+    run_cli::<BaseCircuitBuilder<Fr>>(
+        |stage, pinning, params| create_circuit(&f, private_inputs.clone(), stage, pinning, params),
+        cli,
+    )
+}
+
+/// Adapts `create_circuit`, which can only be called once, to [run_cli]. Every command except `bench` creates a single circuit.
+pub(crate) fn call_once<P, C>(
+    command: &SnarkCmd,
+    create_circuit: impl FnOnce(
+        CircuitBuilderStage,
+        Option<(P, MultiPhaseThreadBreakPoints)>,
+        &ParamsKZG<Bn256>,
+    ) -> C,
+) -> impl Fn(CircuitBuilderStage, Option<(P, MultiPhaseThreadBreakPoints)>, &ParamsKZG<Bn256>) -> C
+{
+    let command = command.to_string();
+    let create_circuit = RefCell::new(Some(create_circuit));
+    move |stage, pinning, params: &ParamsKZG<Bn256>| {
+        let create_circuit = create_circuit.take().unwrap_or_else(|| {
+            panic!("{command} runs the circuit function more than once, use a repeatable entry point such as `run_repeatable`")
+        });
+        create_circuit(stage, pinning, params)
+    }
+}
+
+/// Runs a command that does not run the circuit function, and so does not need the private inputs, on circuits of type `C`.
+pub(crate) fn run_without_inputs<C: ScaffoldCircuit>(cli: Cli)
+where
+    C::Params: Clone + Debug + Serialize + DeserializeOwned,
+{
+    let command = cli.command.to_string();
+    run_cli::<C>(|_, _, _| unreachable!("{command} does not run the circuit function"), cli)
+}
+
+/// Creates the circuit of `f` on `private_inputs` in the given stage, the same way the commands of [run] do.
+///
+/// Outside of the CLI this can be used to benchmark or test a circuit function directly.
+pub fn create_circuit<T, I: PublicInstances>(
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    private_inputs: T,
+    stage: CircuitBuilderStage,
+    pinning: Option<(BaseCircuitParams, MultiPhaseThreadBreakPoints)>,
+    params: &ParamsKZG<Bn256>,
+) -> BaseCircuitBuilder<Fr> {
+    let precircuit = CircuitScaffold { f, private_inputs, _instances: PhantomData };
+    precircuit.create_circuit(stage, pinning, params.k())
+}
 
+/// Turns the `halo2-lib` function `f` into the function that a [Prover] creates circuits with, which creates them like [run] does.
+pub fn circuit_fn<T, I: PublicInstances>(
+    f: impl Fn(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+) -> impl Fn(
+    T,
+    CircuitBuilderStage,
+    Option<(BaseCircuitParams, MultiPhaseThreadBreakPoints)>,
+    &ParamsKZG<Bn256>,
+) -> BaseCircuitBuilder<Fr> {
+    move |private_inputs, stage, pinning, params| {
+        create_circuit(&f, private_inputs, stage, pinning, params)
+    }
+}
+
+/// Runs `f` on each of `inputs` in its own new `Context` in the given phase, with witness generation for all of them done in parallel.
+///
+/// The new threads are created in the order of `inputs` before any of them run, so the break points pinned at keygen
+/// stay valid for witness generation only proving as long as the number of inputs does not change.
+/// The number of worker threads can be set with the `RAYON_NUM_THREADS` env var.
+pub fn parallelize<F, T, R, FR>(
+    builder: &mut BaseCircuitBuilder<F>,
+    phase: usize,
+    inputs: Vec<T>,
+    f: FR,
+) -> Vec<R>
+where
+    F: ScalarField,
+    T: Send,
+    R: Send,
+    FR: Fn(&mut Context<F>, T) -> R + Send + Sync,
+{
+    parallelize_core(builder.pool(phase), inputs, f)
+}
+
+/// Runs the command in `cli` on the circuits returned by `create_circuit`, which is called once for every circuit that the command needs.
+///
+/// The circuit params and break points from the pinning written at keygen are passed to `create_circuit` when proving.
+pub fn run_cli<C: ScaffoldCircuit>(
+    create_circuit: impl Fn(
+        CircuitBuilderStage,
+        Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+        &ParamsKZG<Bn256>,
+    ) -> C,
+    cli: Cli,
+) where
+    C::Params: Clone + Debug + Serialize + DeserializeOwned,
+{
     let name = cli.name;
     let k = cli.degree;
 
@@ -77,12 +366,76 @@ pub fn run_on_inputs<T: DeserializeOwned>(
     fs::create_dir_all(&config_path).unwrap();
     fs::create_dir_all(&data_path).unwrap();
 
-    let params = gen_srs(k);
-    println!("Universal trusted setup (unsafe!) available at: params/kzg_bn254_{k}.srs");
+    let params = load_params(k, cli.seed);
+    println!("Universal trusted setup (unsafe!) available at: {:?}", params_path(k, cli.seed));
+    // the commands below create a single circuit on the inputs captured by `create_circuit`
+    let with_inputs =
+        |_: (),
+         stage: CircuitBuilderStage,
+         pinning: Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+         params: &ParamsKZG<Bn256>| create_circuit(stage, pinning, params);
     match cli.command {
-        SnarkCmd::Mock => {
-            let circuit = precircuit.create_circuit(CircuitBuilderStage::Mock, None, &params);
+        SnarkCmd::Mock { json_path, dump_path } => {
+            let start = Instant::now();
+            let circuit = create_circuit(CircuitBuilderStage::Mock, None, &params);
+            println!("Witness generation time: {:?}", start.elapsed());
+            let prover = MockProver::run(k, &circuit, circuit.instances()).unwrap();
+            if let Some(dump_path) = dump_path {
+                circuit
+                    .with_virtual_trace(|trace| trace.witness_dump().write(&dump_path))
+                    .expect("Witness dumps are only supported for halo2-lib circuits");
+                println!("Witness written to: {dump_path:?}");
+            }
+            let mut report = MockReport::default();
+            if let Err(raw_failures) = prover.verify() {
+                report.raw_failures = raw_failures.iter().map(ToString::to_string).collect();
+                report.failures = circuit
+                    .with_virtual_trace(|trace| trace.map_failures(&raw_failures))
+                    .unwrap_or_default();
+                if !report.failures.is_empty() {
+                    println!("{} constraints are not satisfied:", report.failures.len());
+                    for failure in &report.failures {
+                        println!("  {failure}");
+                    }
+                }
+            }
+            if let Some(json_path) = json_path {
+                report.write(&json_path);
+                println!("Mock prover report written to: {json_path:?}");
+            }
+            // prints the raw failures in terms of the final circuit layout
+            prover.assert_satisfied();
+        }
+        SnarkCmd::SoundnessCheck { json_path } => {
+            let mut circuit = create_circuit(CircuitBuilderStage::Mock, None, &params);
+            // mutations are only meaningful starting from a valid witness
             MockProver::run(k, &circuit, circuit.instances()).unwrap().assert_satisfied();
+            let report = circuit
+                .with_virtual_trace(|trace| soundness_check(trace, k))
+                .expect("The soundness check only supports halo2-lib circuits");
+            println!("{report}");
+            if let Some(json_path) = json_path {
+                report.write(&json_path);
+                println!("Soundness report written to: {json_path:?}");
+            }
+            circuit.clear();
+        }
+        SnarkCmd::Lint { json_path } => {
+            let mut circuit = create_circuit(CircuitBuilderStage::Mock, None, &params);
+            // running the mock prover synthesizes the circuit, which completes the trace of multi-phase circuits
+            MockProver::run(k, &circuit, circuit.instances()).unwrap();
+            let report = circuit
+                .with_virtual_trace(LintReport::new)
+                .expect("Linting is only supported for halo2-lib circuits");
+            println!("{report}");
+            if let Some(json_path) = json_path {
+                report.write(&json_path);
+                println!("Lint report written to: {json_path:?}");
+            }
+            circuit.clear();
+        }
+        SnarkCmd::GenInput { .. } => {
+            panic!("gen-input needs a circuit function whose inputs implement `GenerateInput`, run with `run_with_generator`")
         }
         SnarkCmd::Keygen => {
             let pk_path = data_path.join(PathBuf::from(format!("{name}.pk")));
@@ -90,152 +443,411 @@ pub fn run_on_inputs<T: DeserializeOwned>(
                 fs::remove_file(&pk_path).unwrap();
             }
             let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
-            let circuit = precircuit.create_circuit(CircuitBuilderStage::Keygen, None, &params);
-            let pk = gen_pk(&params, &circuit, None);
-            let c_params = circuit.params();
-            let break_points = circuit.break_points();
-            let mut pinning_file = File::create(&pinning_path)
-                .unwrap_or_else(|_| panic!("Could not create file at {pinning_path:?}"));
-            serde_json::to_writer(&mut pinning_file, &(c_params, break_points))
-                .expect("Could not write pinning file");
-            let mut pk_file = BufWriter::new(
-                File::create(&pk_path)
-                    .unwrap_or_else(|_| panic!("Could not create file at {pk_path:?}")),
-            );
-            pk.write(&mut pk_file, SerdeFormat::RawBytes).expect("Failed to write proving key");
-            println!("Proving key written to: {pk_path:?}");
-
             let vk_path = data_path.join(PathBuf::from(format!("{name}.vk")));
-            let f = File::create(&vk_path)
-                .unwrap_or_else(|_| panic!("Could not create file at {vk_path:?}"));
-            let mut writer = BufWriter::new(f);
-            pk.get_vk()
-                .write(&mut writer, SerdeFormat::RawBytes)
-                .expect("writing vkey should not fail");
+            let scheme = cli.scheme.unwrap_or_default();
+            let prover = Prover::keygen(with_inputs, params, (), scheme);
+            prover.write(&pinning_path, &pk_path, &vk_path);
+            println!("Circuit pinning written to: {pinning_path:?}");
+            println!("Proving key written to: {pk_path:?}");
             println!("Verifying key written to: {vk_path:?}");
         }
-        SnarkCmd::Prove => {
+        SnarkCmd::Witness { output_path } => {
+            let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
+            let pinning = CircuitPinning::<C::Params>::read(&pinning_path);
+            let start = Instant::now();
+            let mut circuit = create_circuit(
+                CircuitBuilderStage::Prover,
+                Some((pinning.params, pinning.break_points)),
+                &params,
+            );
+            println!("Witness generation time: {:?}", start.elapsed());
+            let witness = circuit.witness().expect(
+                "Witness files are only supported for halo2-lib circuits without a second phase",
+            );
+            let output_path = output_path
+                .unwrap_or_else(|| data_path.join(PathBuf::from(format!("{name}.witness"))));
+            witness.write(&output_path);
+            println!("Witness written to: {output_path:?}");
+            circuit.clear();
+        }
+        SnarkCmd::Prove { witness_path } => {
             let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
-            let mut pinning_file = File::open(&pinning_path)
-                .unwrap_or_else(|_| panic!("Could not read file at {pinning_path:?}"));
-            let pinning: (BaseCircuitParams, MultiPhaseThreadBreakPoints) =
-                serde_json::from_reader(&mut pinning_file).expect("Could not read pinning file");
-            let circuit =
-                precircuit.create_circuit(CircuitBuilderStage::Prover, Some(pinning), &params);
             let pk_path = data_path.join(PathBuf::from(format!("{name}.pk")));
-            let pk = custom_read_pk(pk_path, &circuit);
+            let mut prover = Prover::<C, _>::read(with_inputs, params, &pinning_path, &pk_path);
+            if let Some(scheme) = cli.scheme {
+                prover = prover.use_scheme(scheme);
+            }
+            let prover = prover.use_seed(cli.seed);
+            let scheme = prover.scheme();
+            let start = Instant::now();
+            let circuit = match witness_path {
+                Some(witness_path) => {
+                    let witness = WitnessFile::read(&witness_path);
+                    println!("Witness read from: {witness_path:?}");
+                    let pinning = prover.pinning().clone();
+                    C::from_witness(pinning.params, pinning.break_points, witness).expect(
+                        "Witness files are only supported for halo2-lib circuits without a second phase",
+                    )
+                }
+                None => {
+                    let circuit = prover.witness_gen(());
+                    println!("Witness generation time: {:?}", start.elapsed());
+                    circuit
+                }
+            };
             let snark_path = data_path.join(PathBuf::from(format!("{name}.snark")));
             if snark_path.exists() {
                 fs::remove_file(&snark_path).unwrap();
             }
             let start = Instant::now();
-            gen_snark_shplonk(&params, &pk, circuit, Some(&snark_path));
+            let snark = prover.prove_circuit(circuit);
             let prover_time = start.elapsed();
-            println!("Proving time: {:?}", prover_time);
+            println!("Proving time ({scheme}): {:?}", prover_time);
+            write_snark(&snark_path, &snark);
             println!("Snark written to: {snark_path:?}");
         }
-        SnarkCmd::Verify => {
+        SnarkCmd::Verify { snark_paths } => {
+            let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
             let vk_path = data_path.join(PathBuf::from(format!("{name}.vk")));
-            let mut circuit = precircuit.create_circuit(CircuitBuilderStage::Keygen, None, &params);
-            let vk = custom_read_vk(vk_path, &circuit);
-            let snark_path = data_path.join(PathBuf::from(format!("{name}.snark")));
-            let snark = read_snark(&snark_path)
-                .unwrap_or_else(|e| panic!("Snark not found at {snark_path:?}. {e:?}"));
+            let mut verifier = Verifier::read::<C>(params, &pinning_path, &vk_path);
+            if let Some(scheme) = cli.scheme {
+                verifier = verifier.use_scheme(scheme);
+            }
+            let scheme = verifier.scheme();
+            let snark_paths = if snark_paths.is_empty() {
+                vec![data_path.join(PathBuf::from(format!("{name}.snark")))]
+            } else {
+                snark_paths
+            };
+            let snarks = snark_paths
+                .iter()
+                .map(|snark_path| {
+                    read_snark(snark_path)
+                        .unwrap_or_else(|e| panic!("Snark not found at {snark_path:?}. {e:?}"))
+                })
+                .collect::<Vec<_>>();
 
-            let verifier_params = params.verifier_params();
-            let strategy = SingleStrategy::new(&params);
-            let mut transcript =
-                PoseidonTranscript::<NativeLoader, &[u8]>::new::<0>(&snark.proof[..]);
-            let instance = &snark.instances[0][..];
             let start = Instant::now();
-            verify_proof::<
-                KZGCommitmentScheme<Bn256>,
-                VerifierSHPLONK<'_, Bn256>,
-                _,
-                _,
-                SingleStrategy<'_, Bn256>,
-            >(verifier_params, &vk, strategy, &[&[instance]], &mut transcript)
-            .unwrap();
-            let verification_time = start.elapsed();
-            println!("Snark verified successfully in {:?}", verification_time);
+            if let [snark] = &snarks[..] {
+                verifier.verify(snark).unwrap();
+                let verification_time = start.elapsed();
+                println!("Snark verified successfully ({scheme}) in {:?}", verification_time);
+            } else {
+                let result = verifier.batch_verify(&snarks);
+                let verification_time = start.elapsed();
+                match result {
+                    Ok(()) => {}
+                    Err(BatchVerifyError::Invalid(failures)) => {
+                        for (i, e) in &failures {
+                            println!("Snark at {:?} is invalid: {e:?}", snark_paths[*i]);
+                        }
+                        panic!("{} of {} snarks failed verification", failures.len(), snarks.len());
+                    }
+                    Err(BatchVerifyError::Inconsistent) => {
+                        panic!("Batch verification failed, but every snark verifies on its own")
+                    }
+                }
+                println!(
+                    "{} snarks verified successfully ({scheme}) in {:?}",
+                    snarks.len(),
+                    verification_time
+                );
+            }
+        }
+        SnarkCmd::Inspect => {
+            let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
+            let pinning = CircuitPinning::<C::Params>::read(&pinning_path);
+            println!("Circuit pinning at {pinning_path:?}:");
+            println!("{:#?}", pinning.params);
+            for (phase, break_points) in pinning.break_points.iter().enumerate() {
+                println!("Phase {phase} break points: {break_points:?}");
+            }
+            println!("Multi-open scheme: {}", pinning.scheme);
+        }
+        SnarkCmd::Bench { runs, json_path } => {
+            let schemes = match cli.scheme {
+                Some(scheme) => vec![scheme],
+                None => vec![MultiOpenScheme::Shplonk, MultiOpenScheme::Gwc],
+            };
+            let report = bench(&create_circuit, &name, &params, runs, &schemes);
+            println!("{report}");
+            if let Some(json_path) = json_path {
+                report.write(&json_path);
+                println!("Bench report written to: {json_path:?}");
+            }
+        }
+        SnarkCmd::Stats { json_path } => {
+            let stats = circuit_stats(&create_circuit, &name, &params);
+            println!("{stats}");
+            if let Some(json_path) = json_path {
+                stats.write(&json_path);
+                println!("Circuit statistics written to: {json_path:?}");
+            }
+        }
+        SnarkCmd::Diff { baseline_path, rev, current_path, current_rev, max_increase } => {
+            // the other revision runs the same command line, with absolute paths since it runs in its own worktree
+            let revision_args = || {
+                let mut args =
+                    vec!["--name".to_string(), name.clone(), "-k".to_string(), k.to_string()];
+                for (flag, path) in [("--config-path", &config_path), ("--data-path", &data_path)] {
+                    args.push(flag.to_string());
+                    args.push(fs::canonicalize(path).unwrap().to_str().unwrap().to_string());
+                }
+                if let Some(input_path) = &cli.input_path {
+                    args.push("--input".to_string());
+                    args.push(input_path.to_str().unwrap().to_string());
+                }
+                if let Some(seed) = cli.seed {
+                    args.push("--seed".to_string());
+                    args.push(seed.to_string());
+                }
+                if cli.random_input {
+                    args.push("--random-input".to_string());
+                }
+                args
+            };
+            let (baseline_name, baseline) = match (baseline_path, &rev) {
+                (Some(baseline_path), _) => {
+                    (format!("{baseline_path:?}"), DiffInput::read(&baseline_path))
+                }
+                (None, Some(rev)) => (
+                    format!("revision {rev}"),
+                    DiffInput::Stats(stats_at_revision(rev, &revision_args())),
+                ),
+                (None, None) => unreachable!("clap requires a baseline path or a revision"),
+            };
+            let current = match (current_path, &current_rev) {
+                (Some(current_path), _) => DiffInput::read(current_path),
+                (None, Some(current_rev)) => {
+                    DiffInput::Stats(stats_at_revision(current_rev, &revision_args()))
+                }
+                (None, None) => match &baseline {
+                    DiffInput::Stats(_) => {
+                        DiffInput::Stats(circuit_stats(&create_circuit, &name, &params))
+                    }
+                    DiffInput::ConstraintSystem(_) => {
+                        let mut circuit =
+                            create_circuit(CircuitBuilderStage::Keygen, None, &params);
+                        let cs = ConstraintSystemExport::new::<C>(circuit.params());
+                        circuit.clear();
+                        DiffInput::ConstraintSystem(cs)
+                    }
+                },
+            };
+            let diff = match (&baseline, &current) {
+                (DiffInput::Stats(baseline), DiffInput::Stats(current)) => {
+                    StatsDiff::new(baseline, current)
+                }
+                (DiffInput::ConstraintSystem(baseline), DiffInput::ConstraintSystem(current)) => {
+                    StatsDiff::from_constraint_systems(baseline, current)
+                }
+                _ => panic!("Cannot compare circuit statistics with a constraint system export"),
+            };
+            println!("{name} compared to {baseline_name}:");
+            println!("{diff}");
+            if let Some(max_increase) = max_increase {
+                let regressions: Vec<_> = diff
+                    .regressions(max_increase)
+                    .into_iter()
+                    .map(|metric| metric.name.as_str())
+                    .collect();
+                assert!(
+                    regressions.is_empty(),
+                    "{} increased by more than {max_increase}%",
+                    regressions.join(", ")
+                );
+            }
+        }
+        SnarkCmd::ExportCs { output_path } => {
+            let output_path = output_path
+                .unwrap_or_else(|| data_path.join(PathBuf::from(format!("{name}.cs.json"))));
+            let mut circuit = create_circuit(CircuitBuilderStage::Keygen, None, &params);
+            let cs = ConstraintSystemExport::new::<C>(circuit.params());
+            cs.write(&output_path);
+            println!("Constraint system written to: {output_path:?}");
+            circuit.clear();
+        }
+        SnarkCmd::Layout { output_path } => {
+            let output_path = output_path
+                .unwrap_or_else(|| data_path.join(PathBuf::from(format!("{name}.layout.png"))));
+            // the layout does not depend on the witness, so we use the same circuit as for keygen
+            let mut circuit = create_circuit(CircuitBuilderStage::Keygen, None, &params);
+            render_layout(k, &circuit, &output_path);
+            println!("Circuit layout written to: {output_path:?}");
             circuit.clear();
         }
+        SnarkCmd::Export { json_path, instance_format } => {
+            let snark_path = data_path.join(PathBuf::from(format!("{name}.snark")));
+            let snark = read_snark(&snark_path)
+                .unwrap_or_else(|e| panic!("Snark not found at {snark_path:?}. {e:?}"));
+            let json = SnarkJson::from_snark(&snark, instance_format);
+            let (proof, instances) = json.decode().expect("Exported snark JSON should decode");
+            assert!(
+                proof == snark.proof && instances == snark.instances,
+                "Snark JSON round trip is not byte-identical"
+            );
+            let json_path = json_path
+                .unwrap_or_else(|| data_path.join(PathBuf::from(format!("{name}.snark.json"))));
+            write_snark_json(&json_path, &json);
+            println!("Snark JSON written to: {json_path:?}");
+        }
+        SnarkCmd::Import { json_path, output_path } => {
+            let json_path = json_path
+                .unwrap_or_else(|| data_path.join(PathBuf::from(format!("{name}.snark.json"))));
+            let snark_path = output_path
+                .unwrap_or_else(|| data_path.join(PathBuf::from(format!("{name}.snark"))));
+            assert!(
+                !snark_path.exists(),
+                "Snark already exists at {snark_path:?}, remove it or pass a different --output"
+            );
+            let json = read_snark_json(&json_path).unwrap_or_else(|e| panic!("{e}"));
+            let pinning_path = config_path.join(PathBuf::from(format!("{name}.json")));
+            let vk_path = data_path.join(PathBuf::from(format!("{name}.vk")));
+            let verifier = Verifier::read::<C>(params, &pinning_path, &vk_path);
+            let snark =
+                json.to_snark(verifier.params(), verifier.vk()).unwrap_or_else(|e| panic!("{e}"));
+            write_snark(&snark_path, &snark);
+            let written = read_snark(&snark_path)
+                .unwrap_or_else(|e| panic!("Snark not found at {snark_path:?}. {e:?}"));
+            assert!(
+                written.proof == snark.proof && written.instances == snark.instances,
+                "Snark round trip is not byte-identical"
+            );
+            println!("Snark written to: {snark_path:?}");
+        }
     }
 }
 
-fn custom_read_pk<C, P>(fname: P, circuit: &C) -> ProvingKey<G1Affine>
-where
-    C: Circuit<Fr>,
-    P: AsRef<Path>,
-{
-    read_pk::<C>(fname.as_ref(), circuit.params())
-        .unwrap_or_else(|e| panic!("Failed to open file: {:?}: {e:?}", fname.as_ref()))
+/// A ChaCha20 rng seeded with `seed`, or seeded from OS randomness if there is no `seed`.
+pub fn seeded_rng(seed: Option<u64>) -> ChaCha20Rng {
+    match seed {
+        Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+        None => ChaCha20Rng::from_entropy(),
+    }
+}
+
+/// Path of the test SRS for circuits with `2^k` rows, which depends on the `seed` it was generated from.
+///
+/// Like `gen_srs`, the SRS is located in the `PARAMS_DIR` env var if it is set, and in `params` otherwise.
+pub fn params_path(k: u32, seed: Option<u64>) -> PathBuf {
+    let file_name = match seed {
+        Some(seed) => format!("kzg_bn254_{k}_seed_{seed}.srs"),
+        None => format!("kzg_bn254_{k}.srs"),
+    };
+    PathBuf::from(var("PARAMS_DIR").unwrap_or_else(|_| "params".to_string())).join(file_name)
 }
 
-fn custom_read_vk<C, P>(fname: P, circuit: &C) -> VerifyingKey<G1Affine>
+/// Reads the test SRS at [params_path], generating it first if it does not exist.
+///
+/// Without a `seed` this is `gen_srs`. With a `seed` the SRS is generated from [seeded_rng], so it is the same on every machine.
+pub fn load_params(k: u32, seed: Option<u64>) -> ParamsKZG<Bn256> {
+    let Some(seed) = seed else {
+        return gen_srs(k);
+    };
+    let path = params_path(k, Some(seed));
+    if let Ok(f) = File::open(&path) {
+        return ParamsKZG::read(&mut BufReader::new(f))
+            .unwrap_or_else(|e| panic!("Could not read params at {path:?}. {e:?}"));
+    }
+    let params = ParamsKZG::<Bn256>::setup(k, seeded_rng(Some(seed)));
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut writer = BufWriter::new(
+        File::create(&path).unwrap_or_else(|_| panic!("Could not create file at {path:?}")),
+    );
+    params.write(&mut writer).expect("Could not write params");
+    params
+}
+
+/// Creates the circuit for keygen and collects its statistics.
+fn circuit_stats<C: ScaffoldCircuit>(
+    create_circuit: impl Fn(
+        CircuitBuilderStage,
+        Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+        &ParamsKZG<Bn256>,
+    ) -> C,
+    name: &str,
+    params: &ParamsKZG<Bn256>,
+) -> CircuitStats
 where
-    C: Circuit<Fr>,
-    P: AsRef<Path>,
+    C::Params: Serialize,
 {
-    let f = File::open(&fname)
-        .unwrap_or_else(|e| panic!("Failed to open file: {:?}: {e:?}", fname.as_ref()));
-    let mut bufreader = BufReader::new(f);
-    VerifyingKey::read::<_, C>(&mut bufreader, SerdeFormat::RawBytes, circuit.params())
-        .expect("Could not read vkey")
+    let mut circuit = create_circuit(CircuitBuilderStage::Keygen, None, params);
+    // generating the verifying key synthesizes the circuit, which completes the trace of multi-phase circuits
+    let vk = keygen_vk(params, &circuit).expect("Verifying key generation should not fail");
+    let stats = CircuitStats::new(name, params.k(), &circuit, &vk);
+    circuit.clear();
+    stats
 }
 
-impl<T, Fn> CircuitScaffold<T, Fn>
+impl<T, I, Fn> CircuitScaffold<T, I, Fn>
 where
-    Fn: FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
+    I: PublicInstances,
+    Fn: FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I),
 {
     /// Creates a Halo2 circuit from the given function.
     fn create_circuit(
         self,
         stage: CircuitBuilderStage,
         pinning: Option<(BaseCircuitParams, MultiPhaseThreadBreakPoints)>,
-        params: &ParamsKZG<Bn256>,
+        k: u32,
     ) -> BaseCircuitBuilder<Fr> {
-        let mut builder = BaseCircuitBuilder::from_stage(stage);
-        if let Some((params, break_points)) = pinning {
-            builder.set_params(params);
-            builder.set_break_points(break_points);
-        } else {
-            let k = params.k() as usize;
-            // we use env var `LOOKUP_BITS` to determine whether to use `GateThreadBuilder` or `RangeCircuitBuilder`. The difference is that the latter creates a lookup table with 2^LOOKUP_BITS rows, while the former does not.
-            let lookup_bits: Option<usize> = var("LOOKUP_BITS")
-                .map(|str| {
-                    let lookup_bits = str.parse::<usize>().unwrap();
-                    // we use a lookup table with 2^LOOKUP_BITS rows. Due to blinding factors, we need a little more than 2^LOOKUP_BITS rows total in our circuit
-                    assert!(lookup_bits < k, "LOOKUP_BITS needs to be less than DEGREE");
-                    lookup_bits
-                })
-                .ok();
-            // we initiate a "thread builder". This is what keeps track of the execution trace of our program. If not in proving mode, it also keeps track of the ZK constraints.
-            builder.set_k(k);
-            if let Some(lookup_bits) = lookup_bits {
-                builder.set_lookup_bits(lookup_bits);
-            }
-            builder.set_instance_columns(1);
-        };
+        let mut builder = new_builder(stage, pinning, k, I::NUM_COLUMNS);
 
         // builder.main(phase) gets a default "main" thread for the given phase. For most purposes we only need to think about phase 0
         // we need a 64-bit number as input in this case
         // while `some_algorithm_in_zk` was written generically for any field `F`, in practice we use the scalar field of the BN254 curve because that's what the proving system backend uses
-        let mut assigned_instances = vec![];
+        let mut assigned_instances = I::new();
         (self.f)(&mut builder, self.private_inputs, &mut assigned_instances);
-        if !assigned_instances.is_empty() {
-            assert_eq!(builder.assigned_instances.len(), 1, "num_instance_columns != 1");
-            builder.assigned_instances[0] = assigned_instances;
-        }
+        assert_eq!(
+            builder.assigned_instances.len(),
+            I::NUM_COLUMNS,
+            "circuit function uses a different number of instance columns than the pinning"
+        );
+        builder.assigned_instances = assigned_instances.into_columns();
 
         if !stage.witness_gen_only() {
             // now `builder` contains the execution trace, and we are ready to actually create the circuit
-            // minimum rows is the number of rows used for blinding factors. This depends on the circuit itself, but we can guess the number and change it if something breaks (default 9 usually works)
-            let minimum_rows =
-                var("MINIMUM_ROWS").unwrap_or_else(|_| "20".to_string()).parse().unwrap();
-            builder.calculate_params(Some(minimum_rows));
+            builder.calculate_params(Some(minimum_rows()));
         }
 
         builder
     }
 }
+
+/// Creates an empty builder, configured either from the pinning or from `k` and the `LOOKUP_BITS` env var.
+pub(crate) fn new_builder(
+    stage: CircuitBuilderStage,
+    pinning: Option<(BaseCircuitParams, MultiPhaseThreadBreakPoints)>,
+    k: u32,
+    num_instance_columns: usize,
+) -> BaseCircuitBuilder<Fr> {
+    let mut builder = BaseCircuitBuilder::from_stage(stage);
+    if let Some((params, break_points)) = pinning {
+        builder.set_params(params);
+        builder.set_break_points(break_points);
+    } else {
+        let k = k as usize;
+        // we use env var `LOOKUP_BITS` to determine whether to use `GateThreadBuilder` or `RangeCircuitBuilder`. The difference is that the latter creates a lookup table with 2^LOOKUP_BITS rows, while the former does not.
+        let lookup_bits: Option<usize> = var("LOOKUP_BITS")
+            .map(|str| {
+                let lookup_bits = str.parse::<usize>().unwrap();
+                // we use a lookup table with 2^LOOKUP_BITS rows. Due to blinding factors, we need a little more than 2^LOOKUP_BITS rows total in our circuit
+                assert!(lookup_bits < k, "LOOKUP_BITS needs to be less than DEGREE");
+                lookup_bits
+            })
+            .ok();
+        // we initiate a "thread builder". This is what keeps track of the execution trace of our program. If not in proving mode, it also keeps track of the ZK constraints.
+        builder.set_k(k);
+        if let Some(lookup_bits) = lookup_bits {
+            builder.set_lookup_bits(lookup_bits);
+        }
+        builder.set_instance_columns(num_instance_columns);
+    };
+    builder
+}
+
+/// Minimum rows is the number of rows used for blinding factors, read from the `MINIMUM_ROWS` env var.
+/// This depends on the circuit itself, but we can guess the number and change it if something breaks (default 9 usually works)
+pub(crate) fn minimum_rows() -> usize {
+    var("MINIMUM_ROWS").unwrap_or_else(|_| "20".to_string()).parse().unwrap()
+}
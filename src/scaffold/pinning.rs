@@ -0,0 +1,54 @@
+//! The pinning file written at keygen, which pins down everything about the circuit configuration that the prover and verifier need to agree on.
+use std::{fs::File, path::Path};
+
+use clap::ValueEnum;
+use halo2_base::gates::{circuit::BaseCircuitParams, flex_gate::MultiPhaseThreadBreakPoints};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// The KZG multi-open scheme used to batch polynomial openings in the proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiOpenScheme {
+    /// Shplonk from [BDFG20](https://eprint.iacr.org/2020/081)
+    #[default]
+    Shplonk,
+    /// The scheme from [GWC19](https://eprint.iacr.org/2019/953), which has cheaper verification but larger proofs
+    Gwc,
+}
+
+impl std::fmt::Display for MultiOpenScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shplonk => write!(f, "shplonk"),
+            Self::Gwc => write!(f, "gwc"),
+        }
+    }
+}
+
+/// Pinning files written by older versions of the scaffold are a JSON array `[params, break_points]`, which still deserializes into this struct with the default multi-open scheme.
+///
+/// `P` is the `Circuit::Params` of the circuit. Circuits that are not built with `halo2-lib` have no break points.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitPinning<P = BaseCircuitParams> {
+    pub params: P,
+    #[serde(default)]
+    pub break_points: MultiPhaseThreadBreakPoints,
+    #[serde(default)]
+    pub scheme: MultiOpenScheme,
+}
+
+impl<P: Serialize + DeserializeOwned> CircuitPinning<P> {
+    pub fn read(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let pinning_file =
+            File::open(path).unwrap_or_else(|_| panic!("Could not read file at {path:?}"));
+        serde_json::from_reader(pinning_file).expect("Could not read pinning file")
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let pinning_file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        serde_json::to_writer_pretty(pinning_file, self).expect("Could not write pinning file");
+    }
+}
@@ -0,0 +1,240 @@
+//! Proof generation with an optionally seeded prover rng, so that proofs can be reproduced byte for byte,
+//! and a [Prover] that keeps its proving key in memory to prove many times.
+use std::{fs::File, io::BufWriter, path::Path};
+
+use halo2_base::{
+    gates::{circuit::CircuitBuilderStage, flex_gate::MultiPhaseThreadBreakPoints},
+    halo2_proofs::{
+        halo2curves::bn256::{Bn256, Fr, G1Affine},
+        plonk::{create_proof, ProvingKey},
+        poly::kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverGWC, ProverSHPLONK},
+        },
+        SerdeFormat,
+    },
+};
+use serde::{de::DeserializeOwned, Serialize};
+use snark_verifier_sdk::{
+    gen_pk,
+    halo2::PoseidonTranscript,
+    read_pk,
+    snark_verifier::system::halo2::{compile, Config},
+    CircuitExt, NativeLoader, Snark,
+};
+
+use super::{
+    pinning::{CircuitPinning, MultiOpenScheme},
+    seeded_rng,
+    verify::Verifier,
+    ScaffoldCircuit,
+};
+
+/// Generates a snark for `circuit` with the given multi-open scheme, like `gen_snark_shplonk` and `gen_snark_gwc`.
+///
+/// The blinding factors of the prover are sampled from [seeded_rng], so with a `seed` the proof is deterministic.
+pub fn gen_snark<C: CircuitExt<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    scheme: MultiOpenScheme,
+    seed: Option<u64>,
+) -> Snark {
+    let protocol = compile(
+        params,
+        pk.get_vk(),
+        Config::kzg()
+            .with_num_instance(circuit.num_instance())
+            .with_accumulator_indices(C::accumulator_indices()),
+    );
+    let instances = circuit.instances();
+    let instance_refs = instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let rng = seeded_rng(seed);
+    let mut transcript = PoseidonTranscript::<NativeLoader, Vec<u8>>::new::<0>(vec![]);
+    match scheme {
+        MultiOpenScheme::Shplonk => {
+            create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _>(
+                params,
+                pk,
+                &[circuit],
+                &[&instance_refs[..]],
+                rng,
+                &mut transcript,
+            )
+        }
+        MultiOpenScheme::Gwc => {
+            create_proof::<KZGCommitmentScheme<Bn256>, ProverGWC<'_, Bn256>, _, _, _, _>(
+                params,
+                pk,
+                &[circuit],
+                &[&instance_refs[..]],
+                rng,
+                &mut transcript,
+            )
+        }
+    }
+    .expect("Proof generation should not fail");
+    Snark::new(protocol, instances, transcript.finalize())
+}
+
+/// Proves a circuit repeatedly with a proving key, params and pinning that are loaded once and kept in memory.
+///
+/// `create_circuit` creates the circuit from the private inputs, like the function passed to [super::run_cli] but with the inputs as
+/// the first argument. For a `halo2-lib` circuit function use [super::circuit_fn].
+pub struct Prover<C: ScaffoldCircuit, G> {
+    create_circuit: G,
+    params: ParamsKZG<Bn256>,
+    pinning: CircuitPinning<C::Params>,
+    pk: ProvingKey<G1Affine>,
+    scheme: MultiOpenScheme,
+    seed: Option<u64>,
+}
+
+impl<C: ScaffoldCircuit, G> Prover<C, G>
+where
+    C::Params: Clone,
+{
+    /// Uses the multi-open scheme of the pinning.
+    pub fn new(
+        create_circuit: G,
+        params: ParamsKZG<Bn256>,
+        pinning: CircuitPinning<C::Params>,
+        pk: ProvingKey<G1Affine>,
+    ) -> Self {
+        let scheme = pinning.scheme;
+        Self { create_circuit, params, pinning, pk, scheme, seed: None }
+    }
+
+    /// Generates the proving key and pinning for the circuit created from `inputs`, which can be any valid inputs.
+    pub fn keygen<T>(
+        create_circuit: G,
+        params: ParamsKZG<Bn256>,
+        inputs: T,
+        scheme: MultiOpenScheme,
+    ) -> Self
+    where
+        G: Fn(
+            T,
+            CircuitBuilderStage,
+            Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+            &ParamsKZG<Bn256>,
+        ) -> C,
+    {
+        let mut circuit = create_circuit(inputs, CircuitBuilderStage::Keygen, None, &params);
+        let pk = gen_pk(&params, &circuit, None);
+        let pinning = CircuitPinning {
+            params: circuit.params(),
+            break_points: circuit.break_points(),
+            scheme,
+        };
+        circuit.clear();
+        Self::new(create_circuit, params, pinning, pk)
+    }
+
+    /// Reads the pinning and proving key written at keygen.
+    pub fn read(
+        create_circuit: G,
+        params: ParamsKZG<Bn256>,
+        pinning_path: impl AsRef<Path>,
+        pk_path: impl AsRef<Path>,
+    ) -> Self
+    where
+        C::Params: Serialize + DeserializeOwned,
+    {
+        let pinning = CircuitPinning::<C::Params>::read(pinning_path);
+        let pk_path = pk_path.as_ref();
+        let pk = read_pk::<C>(pk_path, pinning.params.clone())
+            .unwrap_or_else(|e| panic!("Failed to open file: {pk_path:?}: {e:?}"));
+        Self::new(create_circuit, params, pinning, pk)
+    }
+
+    /// Overrides the multi-open scheme of the pinning.
+    pub fn use_scheme(mut self, scheme: MultiOpenScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Samples the blinding factors of every proof from [seeded_rng], so proofs are deterministic if `seed` is given.
+    pub fn use_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn params(&self) -> &ParamsKZG<Bn256> {
+        &self.params
+    }
+
+    pub fn pinning(&self) -> &CircuitPinning<C::Params> {
+        &self.pinning
+    }
+
+    pub fn pk(&self) -> &ProvingKey<G1Affine> {
+        &self.pk
+    }
+
+    pub fn scheme(&self) -> MultiOpenScheme {
+        self.scheme
+    }
+
+    /// A [Verifier] for the snarks of this prover.
+    pub fn verifier(&self) -> Verifier {
+        Verifier::new(self.params.clone(), self.pk.get_vk().clone(), self.scheme)
+    }
+
+    /// Writes the pinning, proving key and verifying key, so they can be read back with [Prover::read] and [Verifier::read].
+    pub fn write(
+        &self,
+        pinning_path: impl AsRef<Path>,
+        pk_path: impl AsRef<Path>,
+        vk_path: impl AsRef<Path>,
+    ) where
+        C::Params: Serialize + DeserializeOwned,
+    {
+        self.pinning.write(pinning_path);
+        let create = |path: &Path| {
+            BufWriter::new(
+                File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}")),
+            )
+        };
+        self.pk
+            .write(&mut create(pk_path.as_ref()), SerdeFormat::RawBytes)
+            .expect("Failed to write proving key");
+        self.pk
+            .get_vk()
+            .write(&mut create(vk_path.as_ref()), SerdeFormat::RawBytes)
+            .expect("writing vkey should not fail");
+    }
+
+    /// Generates the witness for `inputs` and proves it.
+    pub fn prove<T>(&self, inputs: T) -> Snark
+    where
+        G: Fn(
+            T,
+            CircuitBuilderStage,
+            Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+            &ParamsKZG<Bn256>,
+        ) -> C,
+    {
+        let circuit = self.witness_gen(inputs);
+        self.prove_circuit(circuit)
+    }
+
+    /// Creates the circuit for `inputs` in the prover stage, configured with the pinning.
+    pub fn witness_gen<T>(&self, inputs: T) -> C
+    where
+        G: Fn(
+            T,
+            CircuitBuilderStage,
+            Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+            &ParamsKZG<Bn256>,
+        ) -> C,
+    {
+        let pinning = (self.pinning.params.clone(), self.pinning.break_points.clone());
+        (self.create_circuit)(inputs, CircuitBuilderStage::Prover, Some(pinning), &self.params)
+    }
+
+    /// Proves a circuit that was already created in the prover stage, for example from a witness file.
+    pub fn prove_circuit(&self, circuit: C) -> Snark {
+        gen_snark(&self.params, &self.pk, circuit, self.scheme, self.seed)
+    }
+}
@@ -0,0 +1,102 @@
+//! Statistics of the circuit at another git revision, for `diff --rev`.
+//!
+//! The revision is checked out into a temporary git worktree, where the same example (or binary) as the current process is
+//! built and run with `stats --json`.
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::stats::CircuitStats;
+
+/// Builds the circuit at git revision `rev` and returns its statistics.
+///
+/// `args` are the CLI arguments before the `stats` command. Since the command runs in the worktree, paths in `args` should be absolute.
+pub fn stats_at_revision(rev: &str, args: &[String]) -> CircuitStats {
+    let cwd = env::current_dir().unwrap();
+    let toplevel = PathBuf::from(git(&cwd, &["rev-parse", "--show-toplevel"]).trim());
+    // the package may be in a subdirectory of the repository
+    let package_dir = cwd.strip_prefix(&toplevel).unwrap_or(Path::new(""));
+
+    let tag = format!(
+        "halo2_scaffold_{}_{}",
+        std::process::id(),
+        rev.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    );
+    let worktree = env::temp_dir().join(&tag);
+    let stats_path = env::temp_dir().join(format!("{tag}.stats.json"));
+    git(&cwd, &["worktree", "add", "--detach", worktree.to_str().unwrap(), rev]);
+
+    let mut cargo = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    cargo.arg("run").args(cargo_target_args());
+    // a target directory of its own, so the build does not replace the running executable, and is reused across runs
+    cargo.env("CARGO_TARGET_DIR", env::temp_dir().join("halo2_scaffold_revision_target"));
+    cargo.arg("--").args(args).args(["stats", "--json", stats_path.to_str().unwrap()]);
+    let status = cargo.current_dir(worktree.join(package_dir)).status();
+
+    git(&cwd, &["worktree", "remove", "--force", worktree.to_str().unwrap()]);
+    let status = status.unwrap_or_else(|e| panic!("Could not run cargo: {e}"));
+    assert!(status.success(), "Could not build and run the circuit at revision {rev}");
+    let stats = CircuitStats::read(&stats_path);
+    let _ = std::fs::remove_file(&stats_path);
+    stats
+}
+
+/// The `cargo run` arguments that select the example or binary of the current process, built with the same profile.
+fn cargo_target_args() -> Vec<String> {
+    let exe = env::current_exe().expect("Could not find the current executable");
+    let name = exe.file_stem().unwrap().to_str().unwrap().to_string();
+    let dir = exe.parent().unwrap();
+    let (kind, profile_dir) = if dir.ends_with("examples") {
+        ("--example", dir.parent().unwrap())
+    } else {
+        ("--bin", dir)
+    };
+    let mut args = vec![kind.to_string(), name];
+    if profile_dir.ends_with("release") {
+        args.push("--release".to_string());
+    }
+    args
+}
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap_or_else(|e| panic!("Could not run git: {e}"));
+    assert!(
+        output.status.success(),
+        "git {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("git output should be UTF-8")
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use crate::scaffold::cmd::{Cli, SnarkCmd};
+
+    #[test]
+    fn test_diff_rev_args() {
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(["test", "--name", "c", "-k", "8", "diff"].iter().chain(args))
+        };
+        let cli = parse(&["--rev", "main", "--current-rev", "HEAD"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            SnarkCmd::Diff { baseline_path: None, rev: Some(rev), current_rev: Some(current_rev), .. }
+                if rev == "main" && current_rev == "HEAD"
+        ));
+        assert!(parse(&["c.stats.json"]).is_ok());
+        // a baseline is required, from either a file or a revision but not both
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["c.stats.json", "--rev", "main"]).is_err());
+        assert!(parse(&["--rev", "main", "--current", "c.stats.json", "--current-rev", "HEAD"])
+            .is_err());
+    }
+}
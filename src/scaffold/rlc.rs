@@ -0,0 +1,443 @@
+//! Scaffolding for two phase circuits that use a random challenge, such as random linear combinations (RLC).
+//!
+//! The first phase function is run like the usual scaffold function. The second phase function gets an [RlcChip] holding the challenge `gamma`,
+//! which is only sampled by the prover after all first phase witnesses have been committed to.
+//! Because the challenge is not known until then, the second phase function is run again during every synthesis of the circuit.
+//!
+//! `halo2-base` v0.4 no longer includes an RLC circuit builder. Its `RlcCircuitBuilder` moved to `axiom-eth`, which depends on its own
+//! pinned versions of `halo2-lib` and `snark-verifier`, so this module implements the small part of it that the scaffold needs on top
+//! of [BaseCircuitBuilder].
+use std::cell::RefCell;
+
+use halo2_base::{
+    gates::{
+        circuit::{
+            builder::BaseCircuitBuilder, BaseCircuitParams, BaseConfig, CircuitBuilderStage,
+            MaybeRangeConfig,
+        },
+        flex_gate::MultiPhaseThreadBreakPoints,
+        GateInstructions,
+    },
+    halo2_proofs::{
+        arithmetic::Field,
+        circuit::{Layouter, SimpleFloorPlanner},
+        halo2curves::bn256::{Bn256, Fr},
+        plonk::{
+            Advice, Challenge, Circuit, Column, ConstraintSystem, Error, FirstPhase, SecondPhase,
+            Selector,
+        },
+        poly::{commitment::Params, kzg::commitment::ParamsKZG, Rotation},
+    },
+    virtual_region::manager::VirtualRegionManager,
+    AssignedValue, Context,
+};
+use serde::de::DeserializeOwned;
+use snark_verifier_sdk::CircuitExt;
+
+use super::{
+    call_once, cmd::Cli, minimum_rows, new_builder, read_inputs, run_cli, run_without_inputs,
+    trace::VirtualTrace, PublicInstances, ScaffoldCircuit,
+};
+
+/// Gives the second phase function access to the challenge `gamma` as a second phase witness.
+#[derive(Clone, Copy, Debug)]
+pub struct RlcChip {
+    gamma: AssignedValue<Fr>,
+}
+
+impl RlcChip {
+    /// `ctx` must be a second phase context. The scaffold constrains the loaded witness to equal the challenge during synthesis.
+    fn new(ctx: &mut Context<Fr>, gamma: Fr) -> Self {
+        assert_eq!(ctx.phase(), 1, "gamma must be loaded in the second phase");
+        Self { gamma: ctx.load_witness(gamma) }
+    }
+
+    pub fn gamma(&self) -> AssignedValue<Fr> {
+        self.gamma
+    }
+
+    /// Computes `values[0] * gamma^{n-1} + values[1] * gamma^{n-2} + ... + values[n-1]` where `n = values.len()`.
+    pub fn compute_rlc(
+        &self,
+        ctx: &mut Context<Fr>,
+        gate: &impl GateInstructions<Fr>,
+        values: impl IntoIterator<Item = AssignedValue<Fr>>,
+    ) -> AssignedValue<Fr> {
+        let mut rlc = ctx.load_zero();
+        for value in values {
+            rlc = gate.mul_add(ctx, rlc, self.gamma, value);
+        }
+        rlc
+    }
+
+    /// Computes the RLC of the first `len` entries of `values`, where `len` is a witness.
+    ///
+    /// Assumes `len <= values.len()`, which should be constrained by the caller.
+    pub fn compute_rlc_var_len(
+        &self,
+        ctx: &mut Context<Fr>,
+        gate: &impl GateInstructions<Fr>,
+        values: &[AssignedValue<Fr>],
+        len: AssignedValue<Fr>,
+    ) -> AssignedValue<Fr> {
+        // indicator[i] = 1 iff i == len
+        let indicator = gate.idx_to_indicator(ctx, len, values.len() + 1);
+        let mut rlc = ctx.load_zero();
+        // is_done = 1 iff i >= len
+        let mut is_done = ctx.load_zero();
+        for (value, ind) in values.iter().zip(indicator) {
+            is_done = gate.add(ctx, is_done, ind);
+            let next = gate.mul_add(ctx, rlc, self.gamma, *value);
+            rlc = gate.select(ctx, rlc, next, is_done);
+        }
+        rlc
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RlcScaffoldConfig {
+    pub base: BaseConfig<Fr>,
+    challenge: Challenge,
+    /// Second phase column holding the challenge, so it can be copied into the second phase `Context`
+    gamma: Column<Advice>,
+    q_gamma: Selector,
+}
+
+impl RlcScaffoldConfig {
+    pub fn configure(meta: &mut ConstraintSystem<Fr>, params: BaseCircuitParams) -> Self {
+        let base = BaseConfig::configure(meta, params);
+        let challenge = meta.challenge_usable_after(FirstPhase);
+        let gamma = meta.advice_column_in(SecondPhase);
+        meta.enable_equality(gamma);
+        let q_gamma = meta.selector();
+        meta.create_gate("gamma = challenge", |meta| {
+            let q = meta.query_selector(q_gamma);
+            let gamma = meta.query_advice(gamma, Rotation::cur());
+            let challenge = meta.query_challenge(challenge);
+            vec![q * (gamma - challenge)]
+        });
+        Self { base, challenge, gamma, q_gamma }
+    }
+}
+
+pub struct RlcCircuit<P, F1> {
+    /// Execution trace of the first phase
+    builder: BaseCircuitBuilder<Fr>,
+    /// Passed from the first phase function to the second phase function, or `None` for a circuit without witnesses
+    payload: Option<P>,
+    second_phase: F1,
+    /// Builder from the latest synthesis, containing both phases
+    synthesized: RefCell<Option<BaseCircuitBuilder<Fr>>>,
+}
+
+impl<P, F1> Circuit<Fr> for RlcCircuit<P, F1>
+where
+    P: Clone,
+    F1: Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P) + Clone,
+{
+    type Config = RlcScaffoldConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = BaseCircuitParams;
+
+    fn params(&self) -> Self::Params {
+        self.builder.config_params.clone()
+    }
+
+    /// A circuit with the same configuration and an empty trace, whose synthesis does not run the second phase function.
+    fn without_witnesses(&self) -> Self {
+        let builder = BaseCircuitBuilder::new(self.builder.witness_gen_only())
+            .use_params(self.builder.config_params.clone());
+        Self {
+            builder,
+            payload: None,
+            second_phase: self.second_phase.clone(),
+            synthesized: RefCell::new(None),
+        }
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        params: Self::Params,
+    ) -> Self::Config {
+        RlcScaffoldConfig::configure(meta, params)
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>) -> Self::Config {
+        unreachable!("You must use configure_with_params")
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let mut builder = self.builder.deep_clone();
+        if let MaybeRangeConfig::WithRange(range) = &config.base.base {
+            range.load_lookup_table(&mut layouter).expect("load lookup table should not fail");
+        }
+        let max_rows = config.base.gate().max_rows;
+        let has_lookups_in_phase = |builder: &BaseCircuitBuilder<Fr>, phase: usize| {
+            builder.lookup_bits().is_some()
+                && builder
+                    .config_params
+                    .num_lookup_advice_per_phase
+                    .get(phase)
+                    .copied()
+                    .unwrap_or(0)
+                    > 0
+        };
+        layouter.assign_region(
+            || "RLC scaffold phase 0",
+            |mut region| {
+                builder.core().phase_manager[0]
+                    .assign_raw(&(config.base.basic_gates(0), max_rows), &mut region);
+                if has_lookups_in_phase(&builder, 0) {
+                    builder.assign_lookups_in_phase(&config.base, &mut region, 0);
+                }
+                Ok(())
+            },
+        )?;
+
+        // the challenge is only available once all first phase columns have been committed to
+        layouter.next_phase();
+        let gamma_value = layouter.get_challenge(config.challenge);
+        // during keygen the challenge is unknown, but we still need to generate a second phase trace of the same shape
+        let mut gamma = Fr::ZERO;
+        gamma_value.map(|value| gamma = value);
+        let rlc = RlcChip::new(builder.main(1), gamma);
+        if let Some(payload) = &self.payload {
+            (self.second_phase)(&mut builder, &rlc, payload.clone());
+        }
+
+        let witness_gen_only = builder.witness_gen_only();
+        layouter.assign_region(
+            || "RLC scaffold phase 1",
+            |mut region| {
+                builder.core().phase_manager[1]
+                    .assign_raw(&(config.base.basic_gates(1), max_rows), &mut region);
+                if has_lookups_in_phase(&builder, 1) {
+                    builder.assign_lookups_in_phase(&config.base, &mut region, 1);
+                }
+                let challenge_cell = region.assign_advice(config.gamma, 0, gamma_value).cell();
+                config.q_gamma.enable(&mut region, 0)?;
+                if !witness_gen_only {
+                    let gamma_cell = *builder
+                        .core()
+                        .copy_manager
+                        .lock()
+                        .unwrap()
+                        .assigned_advices
+                        .get(&rlc.gamma.cell.unwrap())
+                        .expect("gamma should be assigned");
+                    region.constrain_equal(challenge_cell, gamma_cell);
+                    // equality constraints and constants are assigned last, after all advice cells
+                    builder.core().copy_manager.assign_raw(config.base.constants(), &mut region);
+                }
+                Ok(())
+            },
+        )?;
+        builder.assign_instances(&config.base.instance, layouter.namespace(|| "expose"));
+        *self.synthesized.borrow_mut() = Some(builder);
+        Ok(())
+    }
+}
+
+impl<P, F1> CircuitExt<Fr> for RlcCircuit<P, F1>
+where
+    P: Clone,
+    F1: Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P) + Clone,
+{
+    fn num_instance(&self) -> Vec<usize> {
+        self.builder.assigned_instances.iter().map(Vec::len).collect()
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        self.builder
+            .assigned_instances
+            .iter()
+            .map(|column| column.iter().map(|x| *x.value()).collect())
+            .collect()
+    }
+}
+
+impl<P, F1> ScaffoldCircuit for RlcCircuit<P, F1>
+where
+    P: Clone,
+    F1: Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P) + Clone,
+{
+    fn break_points(&self) -> MultiPhaseThreadBreakPoints {
+        self.synthesized
+            .borrow()
+            .as_ref()
+            .expect("circuit must be synthesized before break points are known")
+            .break_points()
+    }
+
+    fn clear(&mut self) {
+        self.builder.clear();
+        if let Some(builder) = self.synthesized.get_mut() {
+            builder.clear();
+        }
+    }
+
+    fn with_virtual_trace<R>(&self, f: impl FnOnce(&VirtualTrace) -> R) -> Option<R> {
+        self.synthesized.borrow().as_ref().map(|builder| f(&VirtualTrace::new(builder)))
+    }
+}
+
+/// Creates a two phase circuit. Outside of witness generation, the second phase is run once here with a placeholder challenge to size the circuit.
+fn create_rlc_circuit<T, I, P, F1>(
+    first_phase: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I) -> P,
+    second_phase: F1,
+    private_inputs: T,
+    stage: CircuitBuilderStage,
+    pinning: Option<(BaseCircuitParams, MultiPhaseThreadBreakPoints)>,
+    params: &ParamsKZG<Bn256>,
+) -> RlcCircuit<P, F1>
+where
+    I: PublicInstances,
+    P: Clone,
+    F1: Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P) + Clone,
+{
+    let mut builder = new_builder(stage, pinning, params.k(), I::NUM_COLUMNS);
+    let mut assigned_instances = I::new();
+    let payload = first_phase(&mut builder, private_inputs, &mut assigned_instances);
+    assert_eq!(
+        builder.assigned_instances.len(),
+        I::NUM_COLUMNS,
+        "circuit function uses a different number of instance columns than the pinning"
+    );
+    builder.assigned_instances = assigned_instances.into_columns();
+
+    if !stage.witness_gen_only() {
+        let mut dry_run = builder.deep_clone();
+        let rlc = RlcChip::new(dry_run.main(1), Fr::ZERO);
+        second_phase(&mut dry_run, &rlc, payload.clone());
+        builder.config_params = dry_run.calculate_params(Some(minimum_rows()));
+        dry_run.clear();
+    }
+
+    RlcCircuit { builder, payload: Some(payload), second_phase, synthesized: RefCell::new(None) }
+}
+
+/// Like [super::run], but for a circuit with a first phase function and a second phase function that has access to a random challenge.
+///
+/// The first phase function returns a payload, usually the assigned values that the second phase needs, which is passed to the second phase function.
+pub fn run_rlc<T, I, P>(
+    first_phase: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I) -> P,
+    second_phase: impl Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P),
+    cli: Cli,
+) where
+    T: DeserializeOwned,
+    I: PublicInstances,
+    P: Clone,
+{
+    if !cli.command.reads_inputs() {
+        return run_rlc_without_inputs::<P, _>(&&second_phase, cli);
+    }
+    let private_inputs = read_inputs(&cli);
+    run_rlc_on_inputs(first_phase, second_phase, cli, private_inputs)
+}
+
+pub fn run_rlc_on_inputs<T, I, P>(
+    first_phase: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I) -> P,
+    second_phase: impl Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P),
+    cli: Cli,
+    private_inputs: T,
+) where
+    T: DeserializeOwned,
+    I: PublicInstances,
+    P: Clone,
+{
+    let second_phase = &second_phase;
+    run_cli::<RlcCircuit<P, _>>(
+        call_once(&cli.command, |stage, pinning, params| {
+            create_rlc_circuit(first_phase, second_phase, private_inputs, stage, pinning, params)
+        }),
+        cli,
+    )
+}
+
+/// Like [run_rlc], but runs the first phase function as many times as the command needs, which the `bench` command requires.
+pub fn run_rlc_repeatable<T, I, P>(
+    first_phase: impl Fn(&mut BaseCircuitBuilder<Fr>, T, &mut I) -> P,
+    second_phase: impl Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P),
+    cli: Cli,
+) where
+    T: DeserializeOwned + Clone,
+    I: PublicInstances,
+    P: Clone,
+{
+    if !cli.command.reads_inputs() {
+        return run_rlc_without_inputs::<P, _>(&&second_phase, cli);
+    }
+    let private_inputs: T = read_inputs(&cli);
+    run_cli::<RlcCircuit<P, _>>(
+        |stage, pinning, params| {
+            create_rlc_circuit(
+                &first_phase,
+                &second_phase,
+                private_inputs.clone(),
+                stage,
+                pinning,
+                params,
+            )
+        },
+        cli,
+    )
+}
+
+/// Runs a command that does not need the private inputs on the RLC circuit with the second phase function `second_phase`,
+/// which only determines the circuit type.
+fn run_rlc_without_inputs<P, F1>(_second_phase: &F1, cli: Cli)
+where
+    P: Clone,
+    F1: Fn(&mut BaseCircuitBuilder<Fr>, &RlcChip, P) + Clone,
+{
+    run_without_inputs::<RlcCircuit<P, F1>>(cli)
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::{
+        gates::{circuit::CircuitBuilderStage, GateChip},
+        halo2_proofs::{
+            dev::MockProver,
+            halo2curves::bn256::{Bn256, Fr},
+            plonk::Circuit,
+            poly::kzg::commitment::ParamsKZG,
+        },
+        AssignedValue,
+    };
+    use rand::rngs::OsRng;
+    use snark_verifier_sdk::CircuitExt;
+
+    use super::create_rlc_circuit;
+
+    #[test]
+    fn test_without_witnesses() {
+        let k = 8;
+        let params = ParamsKZG::<Bn256>::setup(k, OsRng);
+        let circuit = create_rlc_circuit(
+            |builder, values: Vec<u64>, make_public: &mut Vec<AssignedValue<Fr>>| {
+                let values = builder.main(0).assign_witnesses(values.into_iter().map(Fr::from));
+                make_public.push(values[0]);
+                values
+            },
+            |builder, rlc, values| {
+                rlc.compute_rlc(builder.main(1), &GateChip::default(), values);
+            },
+            vec![1, 2, 3],
+            CircuitBuilderStage::Mock,
+            None,
+            &params,
+        );
+        MockProver::run(k, &circuit, circuit.instances()).unwrap().assert_satisfied();
+
+        let empty = circuit.without_witnesses();
+        assert_eq!(empty.params().k, circuit.params().k);
+        assert_eq!(empty.params().num_advice_per_phase, circuit.params().num_advice_per_phase);
+        assert_eq!(empty.num_instance(), vec![0]);
+        MockProver::run(k, &empty, empty.instances()).unwrap().assert_satisfied();
+    }
+}
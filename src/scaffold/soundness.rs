@@ -0,0 +1,426 @@
+//! Searches for under-constrained `halo2-lib` circuits by mutating a valid witness.
+//!
+//! Each equality class of advice cells, i.e. a cell together with all cells copy constrained to it, is set to a new value in turn.
+//! The cells that basic gates compute from it are then recomputed in program order, as if the circuit function had been run
+//! with the mutated value. A mutation that still satisfies every gate, copy constraint and range lookup while changing a public
+//! instance is reported, and then confirmed by running the mock prover on the mutated witness.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    path::Path,
+};
+
+use halo2_base::{
+    halo2_proofs::{
+        dev::MockProver,
+        halo2curves::{bn256::Fr, ff::Field},
+        plonk::Assigned,
+    },
+    utils::fe_to_biguint,
+};
+use serde::Serialize;
+use snark_verifier_sdk::CircuitExt;
+
+use super::{
+    export::{encode_fr, InstanceFormat},
+    trace::{CellLocation, VirtualTrace},
+};
+
+/// Whether the mutated cell is an input of the circuit function or computed by a gate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellKind {
+    /// A witness not computed by any gate, such as one from `ctx.load_witness`. The circuit accepts the mutated value as an
+    /// input, which is only a bug if that value should be rejected, e.g. a non-boolean value that is used as a bit.
+    Input,
+    /// A witness computed by a gate. Its value is not determined by the inputs, which is always a bug.
+    Computed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InstanceChange {
+    pub column: usize,
+    pub row: usize,
+    pub original: String,
+    pub mutated: String,
+}
+
+/// A mutation of the witness that satisfies all constraints of the virtual trace.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Mutation {
+    /// The first cell of the mutated equality class
+    pub cell: CellLocation,
+    pub kind: CellKind,
+    pub original: String,
+    pub mutated: String,
+    pub instances: Vec<InstanceChange>,
+    /// Whether the mock prover accepts the mutated witness. Only checked for single phase circuits
+    pub confirmed: Option<bool>,
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            CellKind::Input => "input",
+            CellKind::Computed => "computed cell",
+        };
+        write!(
+            f,
+            "{kind} at {} can be changed from {} to {}",
+            self.cell, self.original, self.mutated
+        )?;
+        for change in &self.instances {
+            write!(
+                f,
+                ", changing instance {} of column {} from {} to {}",
+                change.row, change.column, change.original, change.mutated
+            )?;
+        }
+        match self.confirmed {
+            Some(true) => write!(f, " (accepted by the mock prover)"),
+            Some(false) => write!(f, " (rejected by the mock prover)"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The result of the `soundness-check` command.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SoundnessReport {
+    /// Number of equality classes that were mutated
+    pub mutations: usize,
+    pub findings: Vec<Mutation>,
+}
+
+impl SoundnessReport {
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        serde_json::to_writer_pretty(file, self).expect("Could not write soundness report");
+    }
+}
+
+impl fmt::Display for SoundnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} mutations change a public instance",
+            self.findings.len(),
+            self.mutations
+        )?;
+        for finding in &self.findings {
+            write!(f, "\n  {finding}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The advice cells of a virtual trace in program order, partitioned into equality classes.
+struct Witness {
+    locations: Vec<CellLocation>,
+    values: Vec<Fr>,
+    parent: Vec<usize>,
+    /// Enabled basic gates `a + b * c = d`, as the indices of `a, b, c, d`
+    gates: Vec<[usize; 4]>,
+    lookups: Vec<usize>,
+    lookup_bits: Option<usize>,
+    /// Index of the advice cell each instance is copied from, by column and row
+    instances: Vec<Vec<Option<usize>>>,
+    /// Roots of the classes that are constrained to equal a constant
+    constants: HashSet<usize>,
+}
+
+impl Witness {
+    fn new(trace: &VirtualTrace) -> Self {
+        let mut locations = vec![];
+        let mut values = vec![];
+        let mut gates = vec![];
+        for ctx in trace.threads() {
+            let start = locations.len();
+            for (offset, value) in ctx.advice.iter().enumerate() {
+                locations.push(CellLocation { phase: ctx.phase(), thread: ctx.id(), offset });
+                values.push(value.evaluate());
+            }
+            for (offset, _) in ctx.selector.iter().enumerate().filter(|(_, enabled)| **enabled) {
+                if offset + 4 <= ctx.advice.len() {
+                    gates.push([0, 1, 2, 3].map(|i| start + offset + i));
+                }
+            }
+        }
+        let index: HashMap<CellLocation, usize> =
+            locations.iter().enumerate().map(|(i, loc)| (*loc, i)).collect();
+        let cell_index = |cell| trace.location(cell).map(|loc| index[&loc]);
+
+        let mut witness = Self {
+            parent: (0..locations.len()).collect(),
+            locations,
+            values,
+            gates,
+            lookups: vec![],
+            lookup_bits: trace.builder().lookup_bits(),
+            instances: vec![],
+            constants: HashSet::new(),
+        };
+        let builder = trace.builder();
+        let constant_cells = {
+            let copy_manager = builder.core().copy_manager.lock().unwrap();
+            for (left, right) in &copy_manager.advice_equalities {
+                if let (Some(left), Some(right)) = (cell_index(*left), cell_index(*right)) {
+                    witness.union(left, right);
+                }
+            }
+            copy_manager
+                .constant_equalities
+                .iter()
+                .filter_map(|(_, cell)| cell_index(*cell))
+                .collect::<Vec<_>>()
+        };
+        let constants = constant_cells.into_iter().map(|i| witness.find(i)).collect();
+        witness.constants = constants;
+        witness.lookups = builder
+            .lookup_manager()
+            .iter()
+            .flat_map(|lookup_manager| lookup_manager.cells_to_lookup.lock().unwrap().clone())
+            .filter_map(|[cell]| cell_index(cell.cell?))
+            .collect();
+        witness.instances = builder
+            .assigned_instances
+            .iter()
+            .map(|column| column.iter().map(|cell| cell.cell.and_then(&cell_index)).collect())
+            .collect();
+        witness
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Keeps the smallest index as the root, so the root of a class is its first cell in program order.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    fn value(&self, roots: &[usize], overlay: &HashMap<usize, Fr>, i: usize) -> Fr {
+        overlay.get(&roots[i]).copied().unwrap_or(self.values[i])
+    }
+
+    /// Sets the class `root` to `value` and recomputes the cells that gates compute from it.
+    /// Returns the new values of all changed classes if the mutated witness satisfies all constraints.
+    fn mutate(&self, roots: &[usize], root: usize, value: Fr) -> Option<HashMap<usize, Fr>> {
+        let mut overlay = HashMap::from([(root, value)]);
+        for gate in &self.gates {
+            let [a, b, c, d] = gate.map(|i| self.value(roots, &overlay, i));
+            if a + b * c == d {
+                continue;
+            }
+            // the cell computed by this gate is the last one whose class first appears here, e.g. `d` in `mul_add`
+            let start = gate[0];
+            let computed = (0..4).rev().find(|&slot| {
+                let class = roots[gate[slot]];
+                class >= start && class != root && !self.constants.contains(&class)
+            })?;
+            let new_value = match computed {
+                0 => d - b * c,
+                1 => Option::<Fr>::from(c.invert()).map(|c_inv| (d - a) * c_inv)?,
+                2 => Option::<Fr>::from(b.invert()).map(|b_inv| (d - a) * b_inv)?,
+                _ => a + b * c,
+            };
+            overlay.insert(roots[gate[computed]], new_value);
+        }
+        let gates_hold = self.gates.iter().all(|gate| {
+            let [a, b, c, d] = gate.map(|i| self.value(roots, &overlay, i));
+            a + b * c == d
+        });
+        let lookups_hold = self.lookup_bits.map_or(true, |lookup_bits| {
+            self.lookups.iter().all(|&i| {
+                fe_to_biguint(&self.value(roots, &overlay, i)).bits() as usize <= lookup_bits
+            })
+        });
+        (gates_hold && lookups_hold).then_some(overlay)
+    }
+}
+
+/// Mutates every equality class of advice cells in the virtual trace of a satisfied circuit, see the module documentation.
+///
+/// Each class is set to `-1`, which is outside the domain of booleans and range checked values. Classes constrained to a
+/// constant are skipped. This takes time quadratic in the size of the circuit, so it is meant for small test inputs.
+pub fn soundness_check(trace: &VirtualTrace, k: u32) -> SoundnessReport {
+    let mut witness = Witness::new(trace);
+    let roots = (0..witness.locations.len()).map(|i| witness.find(i)).collect::<Vec<_>>();
+    let computed = witness
+        .gates
+        .iter()
+        .flat_map(|gate| gate.iter().map(|&i| roots[i]).filter(|&root| root >= gate[0]))
+        .collect::<HashSet<_>>();
+    let classes = roots
+        .iter()
+        .enumerate()
+        .filter(|&(i, &root)| i == root && !witness.constants.contains(&root))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let mut report = SoundnessReport { mutations: classes.len(), findings: vec![] };
+    for root in classes {
+        let original = witness.values[root];
+        let mutated = if original == -Fr::ONE { Fr::ONE } else { -Fr::ONE };
+        let Some(overlay) = witness.mutate(&roots, root, mutated) else { continue };
+        let instances = witness
+            .instances
+            .iter()
+            .enumerate()
+            .flat_map(|(column, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(row, cell)| Some((column, row, (*cell)?)))
+            })
+            .filter_map(|(column, row, i)| {
+                let new_value = witness.value(&roots, &overlay, i);
+                (new_value != witness.values[i]).then(|| InstanceChange {
+                    column,
+                    row,
+                    original: encode_fr(&witness.values[i], InstanceFormat::Hex),
+                    mutated: encode_fr(&new_value, InstanceFormat::Hex),
+                })
+            })
+            .collect::<Vec<_>>();
+        if instances.is_empty() {
+            continue;
+        }
+        report.findings.push(Mutation {
+            cell: witness.locations[root],
+            kind: if computed.contains(&root) { CellKind::Computed } else { CellKind::Input },
+            original: encode_fr(&original, InstanceFormat::Hex),
+            mutated: encode_fr(&mutated, InstanceFormat::Hex),
+            instances,
+            confirmed: confirm(trace, k, &witness.locations, &roots, &overlay),
+        });
+    }
+    report
+}
+
+/// Runs the mock prover on a copy of the builder with the mutation applied. Returns `None` for multi-phase circuits,
+/// whose later phases depend on challenges that the builder alone does not constrain.
+fn confirm(
+    trace: &VirtualTrace,
+    k: u32,
+    locations: &[CellLocation],
+    roots: &[usize],
+    overlay: &HashMap<usize, Fr>,
+) -> Option<bool> {
+    let builder = trace.builder();
+    if builder.core().phase_manager.iter().skip(1).any(|phase| !phase.threads.is_empty()) {
+        return None;
+    }
+    let mut builder = builder.deep_clone();
+    let mutated: HashMap<CellLocation, Fr> = roots
+        .iter()
+        .enumerate()
+        .filter_map(|(i, root)| Some((locations[i], *overlay.get(root)?)))
+        .collect();
+    for (loc, value) in &mutated {
+        builder.pool(loc.phase).threads[loc.thread].advice[loc.offset] = Assigned::Trivial(*value);
+    }
+    // range lookups and instances hold their own copies of the values of the cells they are copied from
+    let mutated_value = |cell: Option<_>| mutated.get(&trace.location(cell?)?).copied();
+    for lookup_manager in builder.lookup_manager() {
+        for [cell] in lookup_manager.cells_to_lookup.lock().unwrap().iter_mut() {
+            if let Some(value) = mutated_value(cell.cell) {
+                cell.value = Assigned::Trivial(value);
+            }
+        }
+    }
+    for cell in builder.assigned_instances.iter_mut().flatten() {
+        if let Some(value) = mutated_value(cell.cell) {
+            cell.value = Assigned::Trivial(value);
+        }
+    }
+    let accepted = MockProver::run(k, &builder, builder.instances()).unwrap().verify().is_ok();
+    builder.clear();
+    Some(accepted)
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::{
+        gates::{
+            circuit::{builder::BaseCircuitBuilder, CircuitBuilderStage},
+            GateChip, GateInstructions,
+        },
+        halo2_proofs::halo2curves::bn256::Fr,
+        AssignedValue,
+    };
+
+    use std::marker::PhantomData;
+
+    use super::{soundness_check, CellKind, SoundnessReport};
+    use crate::scaffold::{trace::VirtualTrace, CircuitScaffold};
+
+    fn check(
+        f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, (), &mut Vec<AssignedValue<Fr>>),
+    ) -> SoundnessReport {
+        let k = 8;
+        // the circuits have no lookups, and a `LOOKUP_BITS` from the environment would add a lookup table to them
+        std::env::remove_var("LOOKUP_BITS");
+        let precircuit = CircuitScaffold { f, private_inputs: (), _instances: PhantomData };
+        let mut builder = precircuit.create_circuit(CircuitBuilderStage::Mock, None, k);
+        let report = soundness_check(&VirtualTrace::new(&builder), k);
+        builder.clear();
+        report
+    }
+
+    #[test]
+    fn test_soundness_check_or() {
+        // a OR b = a + b - a * b, without checking that `a` and `b` are bits
+        let report = check(|builder, _, make_public| {
+            let ctx = builder.main(0);
+            let gate = GateChip::default();
+            let [a, b] = [1, 0].map(|x| ctx.load_witness(Fr::from(x)));
+            let ab = gate.mul(ctx, a, b);
+            let sum = gate.add(ctx, a, b);
+            let out = gate.sub(ctx, sum, ab);
+            make_public.push(out);
+        });
+        // `a = -1` gives `a OR b = -1`, which the mock prover accepts
+        let finding = report.findings.iter().find(|m| m.cell.offset == 0).unwrap();
+        assert_eq!(finding.kind, CellKind::Input);
+        assert_eq!(finding.confirmed, Some(true));
+
+        // with booleanity checks, the inputs can no longer be set to -1
+        let report = check(|builder, _, make_public| {
+            let ctx = builder.main(0);
+            let gate = GateChip::default();
+            let [a, b] = [1, 0].map(|x| ctx.load_witness(Fr::from(x)));
+            gate.assert_bit(ctx, a);
+            gate.assert_bit(ctx, b);
+            let out = gate.or(ctx, a, b);
+            make_public.push(out);
+        });
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn test_soundness_check_unconstrained_output() {
+        // the output is loaded as a witness instead of being computed by a gate
+        let report = check(|builder, _, make_public| {
+            let ctx = builder.main(0);
+            let gate = GateChip::default();
+            let x = ctx.load_witness(Fr::from(3));
+            let x_sq = gate.mul(ctx, x, x);
+            let out = ctx.load_witness(*x_sq.value());
+            make_public.push(out);
+        });
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].cell.offset, 5);
+        assert_eq!(report.findings[0].confirmed, Some(true));
+    }
+}
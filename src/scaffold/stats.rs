@@ -0,0 +1,456 @@
+//! Statistics about the size of a circuit, for tracking the cost of circuits over time.
+use std::{collections::HashSet, fmt, fs::File, path::Path};
+
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Fr, G1Affine},
+    plonk::{Circuit, ConstraintSystem, VerifyingKey},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    constraint_system::{ConstraintSystemExport, GateExport, LookupExport},
+    export::{encode_fr, InstanceFormat},
+    trace::VirtualTrace,
+    ScaffoldCircuit,
+};
+
+/// Columns of the constraint system, which determine the size of the proving key and the proof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnStats {
+    pub advice_per_phase: Vec<usize>,
+    pub fixed: usize,
+    pub instance: usize,
+    pub selectors: usize,
+    pub lookups: usize,
+    pub max_degree: usize,
+}
+
+impl ColumnStats {
+    pub fn new<C: Circuit<Fr>>(params: C::Params) -> Self {
+        let mut cs = ConstraintSystem::default();
+        C::configure_with_params(&mut cs, params);
+        let phases = cs.advice_column_phase();
+        let num_phases = phases.iter().max().map_or(0, |phase| *phase as usize + 1);
+        let mut advice_per_phase = vec![0; num_phases];
+        for phase in phases {
+            advice_per_phase[phase as usize] += 1;
+        }
+        Self {
+            advice_per_phase,
+            fixed: cs.num_fixed_columns(),
+            instance: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            lookups: cs.lookups().len(),
+            max_degree: cs.degree(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadStats {
+    pub phase: usize,
+    pub thread: usize,
+    pub advice_cells: usize,
+    pub gates: usize,
+}
+
+/// Statistics of the virtual trace of a `halo2-lib` circuit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceStats {
+    pub advice_cells_per_phase: Vec<usize>,
+    pub threads: Vec<ThreadStats>,
+    /// Number of enabled `a + b * c = d` gates
+    pub gates: usize,
+    /// Number of copy constraints between two advice cells or between an advice cell and a constant
+    pub copy_constraints: usize,
+    /// Number of distinct constants, each of which takes up a fixed cell
+    pub constants: usize,
+    /// Number of cells queued for range lookups
+    pub lookup_cells: usize,
+}
+
+impl TraceStats {
+    pub fn new(trace: &VirtualTrace) -> Self {
+        let threads: Vec<_> = trace
+            .threads()
+            .map(|ctx| ThreadStats {
+                phase: ctx.phase(),
+                thread: ctx.id(),
+                advice_cells: ctx.advice.len(),
+                gates: ctx.selector.iter().filter(|enabled| **enabled).count(),
+            })
+            .collect();
+        let num_phases = threads.iter().map(|thread| thread.phase + 1).max().unwrap_or(0);
+        let mut advice_cells_per_phase = vec![0; num_phases];
+        for thread in &threads {
+            advice_cells_per_phase[thread.phase] += thread.advice_cells;
+        }
+        let builder = trace.builder();
+        let (copy_constraints, constants) = {
+            let copy_manager = builder.core().copy_manager.lock().unwrap();
+            let constants: HashSet<_> =
+                copy_manager.constant_equalities.iter().map(|(c, _)| c.to_bytes()).collect();
+            (
+                copy_manager.advice_equalities.len() + copy_manager.constant_equalities.len(),
+                constants.len(),
+            )
+        };
+        let lookup_cells = builder
+            .lookup_manager()
+            .iter()
+            .map(|lookup_manager| lookup_manager.cells_to_lookup.lock().unwrap().len())
+            .sum();
+        Self {
+            advice_cells_per_phase,
+            gates: threads.iter().map(|thread| thread.gates).sum(),
+            threads,
+            copy_constraints,
+            constants,
+            lookup_cells,
+        }
+    }
+}
+
+/// Statistics of a circuit, as printed by the `stats` command.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    pub name: String,
+    pub k: u32,
+    /// The `Circuit::Params` of the circuit, for example the `BaseCircuitParams` of a `halo2-lib` circuit
+    pub params: serde_json::Value,
+    pub columns: ColumnStats,
+    /// Number of instances in each instance column
+    pub num_instance: Vec<usize>,
+    /// Only available for circuits built with `halo2-lib`
+    pub trace: Option<TraceStats>,
+    /// Hash of the verifying key used in the proof transcript. Changes whenever the constraints or the fixed columns change
+    pub vk_hash: String,
+}
+
+impl CircuitStats {
+    /// `circuit` must already be synthesized, for example by generating `vk`.
+    pub fn new<C: ScaffoldCircuit>(
+        name: &str,
+        k: u32,
+        circuit: &C,
+        vk: &VerifyingKey<G1Affine>,
+    ) -> Self
+    where
+        C::Params: Serialize,
+    {
+        let params = circuit.params();
+        Self {
+            name: name.to_string(),
+            k,
+            params: serde_json::to_value(&params).expect("Circuit params should serialize"),
+            columns: ColumnStats::new::<C>(params),
+            num_instance: circuit.num_instance(),
+            trace: circuit.with_virtual_trace(TraceStats::new),
+            vk_hash: encode_fr(&vk.transcript_repr(), InstanceFormat::Hex),
+        }
+    }
+
+    /// The cost metrics that are compared by [StatsDiff].
+    pub fn metrics(&self) -> Vec<(String, usize)> {
+        let columns = &self.columns;
+        let mut metrics = vec![
+            ("advice columns".to_string(), columns.advice_per_phase.iter().sum()),
+            ("fixed columns".to_string(), columns.fixed),
+            ("instance columns".to_string(), columns.instance),
+            ("selectors".to_string(), columns.selectors),
+            ("lookup arguments".to_string(), columns.lookups),
+            ("max gate degree".to_string(), columns.max_degree),
+            ("instances".to_string(), self.num_instance.iter().sum()),
+        ];
+        if let Some(trace) = &self.trace {
+            for (phase, cells) in trace.advice_cells_per_phase.iter().enumerate() {
+                metrics.push((format!("advice cells (phase {phase})"), *cells));
+            }
+            metrics.extend([
+                ("enabled gates".to_string(), trace.gates),
+                ("copy constraints".to_string(), trace.copy_constraints),
+                ("constants".to_string(), trace.constants),
+                ("lookup cells".to_string(), trace.lookup_cells),
+            ]);
+        }
+        metrics
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let file = File::open(path).unwrap_or_else(|_| panic!("Could not read file at {path:?}"));
+        serde_json::from_reader(file).expect("Could not read circuit stats")
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        serde_json::to_writer_pretty(file, self).expect("Could not write circuit stats");
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Circuit statistics for {} (k = {})", self.name, self.k)?;
+        let columns = &self.columns;
+        for (phase, num_advice) in columns.advice_per_phase.iter().enumerate() {
+            writeln!(f, "  {:<32}{num_advice}", format!("advice columns (phase {phase})"))?;
+        }
+        writeln!(f, "  {:<32}{}", "fixed columns", columns.fixed)?;
+        writeln!(f, "  {:<32}{}", "instance columns", columns.instance)?;
+        writeln!(f, "  {:<32}{}", "selectors", columns.selectors)?;
+        writeln!(f, "  {:<32}{}", "lookup arguments", columns.lookups)?;
+        writeln!(f, "  {:<32}{}", "max gate degree", columns.max_degree)?;
+        writeln!(f, "  {:<32}{}", "instances", self.num_instance.iter().sum::<usize>())?;
+        if let Some(trace) = &self.trace {
+            for (phase, cells) in trace.advice_cells_per_phase.iter().enumerate() {
+                writeln!(f, "  {:<32}{cells}", format!("advice cells (phase {phase})"))?;
+            }
+            writeln!(f, "  {:<32}{}", "enabled gates", trace.gates)?;
+            writeln!(f, "  {:<32}{}", "copy constraints", trace.copy_constraints)?;
+            writeln!(f, "  {:<32}{}", "constants", trace.constants)?;
+            writeln!(f, "  {:<32}{}", "lookup cells", trace.lookup_cells)?;
+            writeln!(f, "  Threads:")?;
+            writeln!(f, "    {:<8}{:<8}{:<16}{}", "phase", "thread", "advice cells", "gates")?;
+            for thread in &trace.threads {
+                writeln!(
+                    f,
+                    "    {:<8}{:<8}{:<16}{}",
+                    thread.phase, thread.thread, thread.advice_cells, thread.gates
+                )?;
+            }
+        }
+        writeln!(f, "  vk hash: {}", self.vk_hash)?;
+        write!(f, "  Params: {}", self.params)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetricDiff {
+    pub name: String,
+    /// `None` if the metric only exists in the other stats, for example advice cells of a phase that was added
+    pub baseline: Option<usize>,
+    pub current: Option<usize>,
+}
+
+impl MetricDiff {
+    /// Increase from the baseline in percent, where a metric that is missing counts as `0`.
+    pub fn increase_percent(&self) -> f64 {
+        let [baseline, current] = [self.baseline, self.current].map(|x| x.unwrap_or(0) as f64);
+        if baseline == current {
+            0.0
+        } else if baseline == 0.0 {
+            f64::INFINITY
+        } else {
+            (current - baseline) / baseline * 100.0
+        }
+    }
+}
+
+/// Differences in cost between a baseline and the current version of the same circuit, either of their [CircuitStats] or of their
+/// [ConstraintSystemExport]s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsDiff {
+    pub metrics: Vec<MetricDiff>,
+    /// Whether the verifying key hash changed, only known when comparing [CircuitStats]
+    pub vk_changed: Option<bool>,
+    /// Gates and lookups that were removed (`-`) or added (`+`), only known when comparing [ConstraintSystemExport]s
+    pub constraint_changes: Vec<String>,
+}
+
+impl StatsDiff {
+    pub fn new(baseline: &CircuitStats, current: &CircuitStats) -> Self {
+        Self {
+            metrics: diff_metrics(baseline.metrics(), current.metrics()),
+            vk_changed: Some(baseline.vk_hash != current.vk_hash),
+            constraint_changes: vec![],
+        }
+    }
+
+    pub fn from_constraint_systems(
+        baseline: &ConstraintSystemExport,
+        current: &ConstraintSystemExport,
+    ) -> Self {
+        let fmt_gate = |gate: &GateExport| {
+            let constraints: Vec<_> =
+                gate.constraints.iter().map(|c| c.expression.as_str()).collect();
+            format!("gate {}: {}", gate.name, constraints.join(", "))
+        };
+        let fmt_lookup = |lookup: &LookupExport| {
+            format!(
+                "lookup {}: ({}) in ({})",
+                lookup.name,
+                lookup.inputs.join(", "),
+                lookup.tables.join(", ")
+            )
+        };
+        let mut constraint_changes = vec![];
+        for (removed, added, sign) in [(baseline, current, '-'), (current, baseline, '+')] {
+            constraint_changes.extend(
+                removed
+                    .gates
+                    .iter()
+                    .filter(|gate| !added.gates.contains(gate))
+                    .map(|gate| format!("{sign} {}", fmt_gate(gate))),
+            );
+            constraint_changes.extend(
+                removed
+                    .lookups
+                    .iter()
+                    .filter(|lookup| !added.lookups.contains(lookup))
+                    .map(|lookup| format!("{sign} {}", fmt_lookup(lookup))),
+            );
+        }
+        Self {
+            metrics: diff_metrics(baseline.metrics(), current.metrics()),
+            vk_changed: None,
+            constraint_changes,
+        }
+    }
+
+    /// Metrics that increased by more than `max_increase_percent` from the baseline.
+    pub fn regressions(&self, max_increase_percent: f64) -> Vec<&MetricDiff> {
+        self.metrics
+            .iter()
+            .filter(|metric| metric.increase_percent() > max_increase_percent)
+            .collect()
+    }
+}
+
+/// Pairs up the metrics with the same name, keeping the order of the baseline followed by metrics that are new.
+fn diff_metrics(baseline: Vec<(String, usize)>, current: Vec<(String, usize)>) -> Vec<MetricDiff> {
+    let mut metrics: Vec<_> = baseline
+        .iter()
+        .map(|(name, value)| MetricDiff {
+            name: name.clone(),
+            baseline: Some(*value),
+            current: current.iter().find(|(n, _)| n == name).map(|(_, v)| *v),
+        })
+        .collect();
+    for (name, value) in current {
+        if !metrics.iter().any(|metric| metric.name == name) {
+            metrics.push(MetricDiff { name, baseline: None, current: Some(value) });
+        }
+    }
+    metrics
+}
+
+impl fmt::Display for StatsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmt_value = |value: Option<usize>| value.map_or("-".to_string(), |v| v.to_string());
+        write!(f, "  {:<32}{:>12}{:>12}{:>10}", "metric", "baseline", "current", "change")?;
+        for metric in &self.metrics {
+            let change = match metric.increase_percent() {
+                x if x == 0.0 => String::new(),
+                x if x.is_infinite() => "new".to_string(),
+                x => format!("{x:+.1}%"),
+            };
+            write!(
+                f,
+                "\n  {:<32}{:>12}{:>12}{:>10}",
+                metric.name,
+                fmt_value(metric.baseline),
+                fmt_value(metric.current),
+                change
+            )?;
+        }
+        if let Some(vk_changed) = self.vk_changed {
+            write!(f, "\n  vk hash {}", if vk_changed { "changed" } else { "unchanged" })?;
+        }
+        for change in &self.constraint_changes {
+            write!(f, "\n  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The input of the `diff` command: statistics written by `stats --json` or a constraint system written by `export-cs` as JSON.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffInput {
+    Stats(CircuitStats),
+    ConstraintSystem(ConstraintSystemExport),
+}
+
+impl DiffInput {
+    /// Reads either kind of file, telling them apart by their fields.
+    pub fn read(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let file = File::open(path).unwrap_or_else(|_| panic!("Could not read file at {path:?}"));
+        let value: serde_json::Value = serde_json::from_reader(file).unwrap_or_else(|e| {
+            panic!("{path:?} is not a JSON file written by `stats --json` or `export-cs`: {e}")
+        });
+        if value.get("gates").is_some() {
+            Self::ConstraintSystem(
+                serde_json::from_value(value).expect("Could not read constraint system"),
+            )
+        } else {
+            Self::Stats(serde_json::from_value(value).expect("Could not read circuit stats"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::{CircuitStats, ColumnStats, StatsDiff};
+    use crate::{
+        scaffold::constraint_system::{ConstraintSystemExport, LookupExport},
+        vanilla_circuits::standard_plonk::StandardPlonk,
+    };
+
+    fn stats(advice: usize, lookups: usize, vk_hash: &str) -> CircuitStats {
+        CircuitStats {
+            name: "test".to_string(),
+            k: 10,
+            params: serde_json::Value::Null,
+            columns: ColumnStats {
+                advice_per_phase: vec![advice],
+                fixed: 1,
+                instance: 1,
+                selectors: 0,
+                lookups,
+                max_degree: 4,
+            },
+            num_instance: vec![2],
+            trace: None,
+            vk_hash: vk_hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_stats_diff() {
+        let diff = StatsDiff::new(&stats(10, 0, "0x01"), &stats(11, 1, "0x02"));
+        assert_eq!(diff.vk_changed, Some(true));
+        let regressions: Vec<_> =
+            diff.regressions(5.0).into_iter().map(|metric| metric.name.as_str()).collect();
+        assert_eq!(regressions, vec!["advice columns", "lookup arguments"]);
+        assert!(diff.regressions(f64::MAX).is_empty());
+
+        let diff = StatsDiff::new(&stats(10, 1, "0x01"), &stats(9, 1, "0x01"));
+        assert_eq!(diff.vk_changed, Some(false));
+        assert!(diff.regressions(0.0).is_empty());
+    }
+
+    #[test]
+    fn test_constraint_system_diff() {
+        let baseline = ConstraintSystemExport::new::<StandardPlonk<Fr>>(());
+        let mut current = baseline.clone();
+        current.lookups.push(LookupExport {
+            name: "range".to_string(),
+            inputs: vec!["advice[0]".to_string()],
+            tables: vec!["fixed[0]".to_string()],
+        });
+        let diff = StatsDiff::from_constraint_systems(&baseline, &current);
+        assert_eq!(diff.vk_changed, None);
+        assert_eq!(diff.constraint_changes, vec!["+ lookup range: (advice[0]) in (fixed[0])"]);
+        let regressions: Vec<_> =
+            diff.regressions(0.0).into_iter().map(|metric| metric.name.as_str()).collect();
+        assert_eq!(regressions, vec!["lookup arguments"]);
+
+        let diff = StatsDiff::from_constraint_systems(&current, &baseline);
+        assert_eq!(diff.constraint_changes, vec!["- lookup range: (advice[0]) in (fixed[0])"]);
+        assert!(diff.regressions(0.0).is_empty());
+    }
+}
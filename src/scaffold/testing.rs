@@ -0,0 +1,308 @@
+//! Helpers for tests asserting that a circuit rejects a bad witness with a specific failure, instead of just any failure,
+//! for differential tests of a circuit against a native implementation of the same function, and for snapshot tests.
+use std::{
+    env::var,
+    fmt,
+    fs::{self, File},
+    marker::PhantomData,
+    path::Path,
+};
+
+use halo2_base::{
+    gates::{
+        circuit::{builder::BaseCircuitBuilder, CircuitBuilderStage},
+        flex_gate::MultiPhaseThreadBreakPoints,
+    },
+    halo2_proofs::{
+        dev::{MockProver, VerifyFailure},
+        halo2curves::bn256::{Bn256, Fr},
+        plonk::{keygen_vk, Circuit},
+        poly::{commitment::Params, kzg::commitment::ParamsKZG},
+    },
+};
+use serde::{Deserialize, Serialize};
+use snark_verifier_sdk::CircuitExt;
+
+use super::{
+    stats::CircuitStats,
+    trace::{failure_row, ConstraintFailure, VirtualTrace},
+    CircuitScaffold, PublicInstances, ScaffoldCircuit,
+};
+
+/// Name of the only gate of `halo2-lib` circuits, in the virtual trace.
+const BASIC_GATE: &str = "a + b * c = d";
+
+/// A failure that the mock prover is expected to report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExpectedFailure {
+    /// A constraint of a gate whose name contains `name` is not satisfied.
+    ///
+    /// For vanilla circuits `offset` is the offset within the region that the gate is enabled in, or the row if the gate
+    /// is enabled outside of any region. For `halo2-lib` circuits it is the offset within the `Context`, and the gate is `a + b * c = d`.
+    Gate { name: String, offset: Option<usize> },
+    /// A lookup argument is not satisfied
+    Lookup,
+    /// A copy constraint, or a constraint that a cell equals a constant, is not satisfied
+    Permutation,
+}
+
+impl ExpectedFailure {
+    /// A failure of the gate whose name contains `name`, at any offset.
+    pub fn gate(name: impl Into<String>) -> Self {
+        Self::Gate { name: name.into(), offset: None }
+    }
+
+    /// Restricts an expected gate failure to the given offset.
+    pub fn at(self, offset: usize) -> Self {
+        match self {
+            Self::Gate { name, .. } => Self::Gate { name, offset: Some(offset) },
+            _ => panic!("Only gate failures have an offset"),
+        }
+    }
+
+    pub fn matches(&self, failure: &VerifyFailure) -> bool {
+        match (self, failure) {
+            (
+                Self::Gate { name, offset },
+                VerifyFailure::ConstraintNotSatisfied { constraint, location, .. },
+            ) => {
+                constraint.to_string().contains(name.as_str())
+                    && offset.map_or(true, |offset| offset == failure_row(location))
+            }
+            (Self::Lookup, VerifyFailure::Lookup { .. }) => true,
+            (Self::Permutation, VerifyFailure::Permutation { .. }) => true,
+            _ => false,
+        }
+    }
+
+    /// Matches a failure found in the virtual trace of a `halo2-lib` circuit.
+    pub fn matches_virtual(&self, failure: &ConstraintFailure) -> bool {
+        match (self, failure) {
+            (Self::Gate { name, offset }, ConstraintFailure::Gate { cell, .. }) => {
+                BASIC_GATE.contains(name.as_str())
+                    && offset.map_or(true, |offset| offset == cell.offset)
+            }
+            (Self::Lookup, ConstraintFailure::Lookup { .. }) => true,
+            (
+                Self::Permutation,
+                ConstraintFailure::Copy { .. } | ConstraintFailure::Constant { .. },
+            ) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ExpectedFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gate { name, offset: Some(offset) } => {
+                write!(f, "gate `{name}` at offset {offset}")
+            }
+            Self::Gate { name, offset: None } => write!(f, "gate `{name}`"),
+            Self::Lookup => write!(f, "lookup"),
+            Self::Permutation => write!(f, "permutation"),
+        }
+    }
+}
+
+/// Runs the mock prover on `circuit` and asserts that every failure in `expected` is among the failures it reports.
+pub fn assert_rejects<C: Circuit<Fr>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<Fr>>,
+    expected: &[ExpectedFailure],
+) {
+    let failures = MockProver::run(k, circuit, instances)
+        .unwrap()
+        .verify()
+        .expect_err("Circuit should not be satisfied");
+    for expected in expected {
+        assert!(
+            failures.iter().any(|failure| expected.matches(failure)),
+            "Expected {expected} to fail, but the mock prover reported:\n{}",
+            failures.iter().map(|failure| format!("  {failure}")).collect::<Vec<_>>().join("\n")
+        );
+    }
+}
+
+/// Like [assert_rejects], but for the circuit created by the `halo2-lib` function `f` on `private_inputs`, as in [super::run].
+///
+/// The expected failures are matched against the virtual trace, so gate offsets are offsets within the `Context`.
+/// Uses the `LOOKUP_BITS` env var like the CLI does.
+pub fn assert_rejects_fn<T, I: PublicInstances>(
+    k: u32,
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    private_inputs: T,
+    expected: &[ExpectedFailure],
+) {
+    let precircuit = CircuitScaffold { f, private_inputs, _instances: PhantomData };
+    let mut builder = precircuit.create_circuit(CircuitBuilderStage::Mock, None, k);
+    let prover = MockProver::run(k, &builder, builder.instances()).unwrap();
+    let raw_failures = prover.verify().expect_err("Circuit should not be satisfied");
+    let failures = VirtualTrace::new(&builder).map_failures(&raw_failures);
+    for expected in expected {
+        assert!(
+            failures.iter().any(|failure| expected.matches_virtual(failure)),
+            "Expected {expected} to fail, but the virtual trace has:\n{}",
+            failures.iter().map(|failure| format!("  {failure}")).collect::<Vec<_>>().join("\n")
+        );
+    }
+    builder.clear();
+}
+
+/// Runs the mock prover on the circuit created by the `halo2-lib` function `f` on `private_inputs`, as in [super::run].
+///
+/// Returns the public instances of the circuit if all constraints are satisfied, and the failures otherwise.
+pub fn mock_instances<T, I: PublicInstances>(
+    k: u32,
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    private_inputs: T,
+) -> Result<Vec<Vec<Fr>>, Vec<VerifyFailure>> {
+    let precircuit = CircuitScaffold { f, private_inputs, _instances: PhantomData };
+    let mut builder = precircuit.create_circuit(CircuitBuilderStage::Mock, None, k);
+    let instances = builder.instances();
+    let result = MockProver::run(k, &builder, instances.clone()).unwrap().verify();
+    builder.clear();
+    result.map(|_| instances)
+}
+
+/// Asserts that the `halo2-lib` function `f` computes the same public instances on `private_inputs` as the native function `native`.
+///
+/// `native` returns `None` for inputs that the circuit should reject, in which case the mock prover must not be satisfied.
+/// This is meant to be called from property based tests with randomly generated inputs.
+pub fn assert_matches_native<T: Clone + fmt::Debug, I: PublicInstances>(
+    k: u32,
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut I),
+    native: impl FnOnce(T) -> Option<Vec<Vec<Fr>>>,
+    private_inputs: T,
+) {
+    let expected = native(private_inputs.clone());
+    match (mock_instances(k, f, private_inputs.clone()), expected) {
+        (Ok(instances), Some(expected)) => assert_eq!(
+            instances, expected,
+            "Circuit and native function disagree on input {private_inputs:?}"
+        ),
+        (Ok(instances), None) => panic!(
+            "Circuit accepts input {private_inputs:?} with instances {instances:?}, but the native function rejects it"
+        ),
+        (Err(failures), Some(_)) => panic!(
+            "Circuit rejects input {private_inputs:?}, but the native function accepts it:\n{}",
+            failures.iter().map(|failure| format!("  {failure}")).collect::<Vec<_>>().join("\n")
+        ),
+        (Err(_), None) => {}
+    }
+}
+
+/// Everything about a circuit that a snapshot test checks: the vk hash, the circuit params, the cell counts and the break points.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CircuitSnapshot {
+    #[serde(flatten)]
+    pub stats: CircuitStats,
+    pub break_points: MultiPhaseThreadBreakPoints,
+}
+
+impl CircuitSnapshot {
+    /// Generates the verifying key of `circuit`, which must be created in the keygen stage, to take its snapshot.
+    pub fn new<C: ScaffoldCircuit>(name: &str, params: &ParamsKZG<Bn256>, mut circuit: C) -> Self
+    where
+        C::Params: Serialize,
+    {
+        let vk = keygen_vk(params, &circuit).expect("Verifying key generation should not fail");
+        let snapshot = Self {
+            stats: CircuitStats::new(name, params.k(), &circuit, &vk),
+            break_points: circuit.break_points(),
+        };
+        circuit.clear();
+        snapshot
+    }
+}
+
+/// Asserts that the snapshot of `circuit` matches the snapshot file at `path`.
+///
+/// `circuit` must be created in the keygen stage. The vk hash depends on `params`, so these should be the seeded test SRS of [super::load_params].
+/// If the `UPDATE_SNAPSHOTS` env var is `1`, the snapshot is written to `path` instead. A missing snapshot file fails the assertion,
+/// so that a snapshot that was never committed cannot silently pass.
+pub fn assert_snapshot<C: ScaffoldCircuit>(
+    path: impl AsRef<Path>,
+    name: &str,
+    params: &ParamsKZG<Bn256>,
+    circuit: C,
+) where
+    C::Params: Serialize,
+{
+    let path = path.as_ref();
+    let snapshot = CircuitSnapshot::new(name, params, circuit);
+    if var("UPDATE_SNAPSHOTS").map_or(false, |v| v == "1") {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        serde_json::to_writer_pretty(file, &snapshot).expect("Could not write snapshot");
+        println!("Snapshot written to: {path:?}");
+        return;
+    }
+    assert!(
+        path.exists(),
+        "Snapshot of circuit {name} not found at {path:?}, run with UPDATE_SNAPSHOTS=1 to write it"
+    );
+    let file = File::open(path).unwrap_or_else(|_| panic!("Could not read file at {path:?}"));
+    let expected: serde_json::Value =
+        serde_json::from_reader(file).expect("Snapshot should be valid JSON");
+    let actual = serde_json::to_value(&snapshot).unwrap();
+    if expected != actual {
+        let changes = actual
+            .as_object()
+            .unwrap()
+            .iter()
+            .filter(|(key, value)| expected.get(key.as_str()) != Some(*value))
+            .map(|(key, value)| {
+                let old = expected.get(key.as_str()).unwrap_or(&serde_json::Value::Null);
+                format!("  {key}: {old} -> {value}")
+            })
+            .collect::<Vec<_>>();
+        panic!(
+            "Circuit {name} does not match the snapshot at {path:?}:\n{}\nIf the change is expected, rerun with UPDATE_SNAPSHOTS=1",
+            changes.join("\n")
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::{
+        gates::circuit::builder::BaseCircuitBuilder,
+        halo2_proofs::halo2curves::bn256::Fr,
+        AssignedValue,
+        QuantumCell::{Constant, Existing, Witness},
+    };
+
+    use super::{assert_rejects_fn, ExpectedFailure};
+
+    #[test]
+    fn test_rejects_forged_gate() {
+        let f = |builder: &mut BaseCircuitBuilder<Fr>,
+                 x: Fr,
+                 make_public: &mut Vec<AssignedValue<Fr>>| {
+            let ctx = builder.main(0);
+            let x = ctx.load_witness(x);
+            // x^2 + 72 with a forged output
+            let out = ctx.assign_region_last(
+                [Constant(Fr::from(72)), Existing(x), Existing(x), Witness(Fr::from(1))],
+                [0],
+            );
+            make_public.push(out);
+        };
+        assert_rejects_fn(8, f, Fr::from(3), &[ExpectedFailure::gate("a + b * c").at(1)]);
+    }
+
+    #[test]
+    fn test_rejects_forged_copy() {
+        let f = |builder: &mut BaseCircuitBuilder<Fr>,
+                 (x, y): (Fr, Fr),
+                 _: &mut Vec<AssignedValue<Fr>>| {
+            let ctx = builder.main(0);
+            let [x, y] = [x, y].map(|v| ctx.load_witness(v));
+            ctx.constrain_equal(&x, &y);
+        };
+        assert_rejects_fn(8, f, (Fr::from(1), Fr::from(2)), &[ExpectedFailure::Permutation]);
+    }
+}
@@ -0,0 +1,537 @@
+//! A virtual view of the execution trace in a [BaseCircuitBuilder], where each cell is identified by the `Context` it was assigned in
+//! instead of by its row and column in the final circuit.
+//!
+//! Constraint failures found here map directly back to user code: the phase, the thread (the `Context` id) and the offset of the cell within the thread.
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use halo2_base::{
+    gates::circuit::builder::BaseCircuitBuilder,
+    halo2_proofs::{
+        dev::{FailureLocation, VerifyFailure},
+        halo2curves::bn256::Fr,
+    },
+    utils::fe_to_biguint,
+    Context, ContextCell,
+};
+use serde::Serialize;
+
+use super::export::{encode_fr, InstanceFormat};
+
+/// Location of a cell in the virtual trace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct CellLocation {
+    pub phase: usize,
+    /// The id of the `Context` within its phase, where `0` is the main thread returned by `builder.main(phase)`
+    pub thread: usize,
+    /// Offset of the cell within the `Context`
+    pub offset: usize,
+}
+
+impl fmt::Display for CellLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "phase {}, thread {}, offset {}", self.phase, self.thread, self.offset)
+    }
+}
+
+/// A constraint that is not satisfied by the virtual trace. Field elements are hex encoded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConstraintFailure {
+    /// The basic gate `a + b * c = d` enabled at `cell`, where `a` is at `cell` and `b, c, d` are the next three cells
+    Gate { cell: CellLocation, values: [String; 4] },
+    /// Two cells that should be equal by a copy constraint
+    Copy { left: CellLocation, right: CellLocation, left_value: String, right_value: String },
+    /// A cell that should equal a constant
+    Constant { cell: CellLocation, value: String, constant: String },
+    /// A cell queued for a range lookup whose value is not in `[0, 2^lookup_bits)`
+    Lookup { cell: CellLocation, value: String, lookup_bits: usize },
+    /// A failure reported by the mock prover that could not be mapped to a cell of the virtual trace
+    Unmapped { failure: String },
+}
+
+impl fmt::Display for ConstraintFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gate { cell, values: [a, b, c, d] } => {
+                write!(f, "gate `a + b * c = d` enabled at {cell} is not satisfied: {a} + {b} * {c} != {d}")
+            }
+            Self::Copy { left, right, left_value, right_value } => write!(
+                f,
+                "copy constraint is not satisfied: {left_value} at {left} != {right_value} at {right}"
+            ),
+            Self::Constant { cell, value, constant } => {
+                write!(f, "cell at {cell} has value {value} but should equal constant {constant}")
+            }
+            Self::Lookup { cell, value, lookup_bits } => write!(
+                f,
+                "cell at {cell} has value {value}, which is not in the lookup table [0, 2^{lookup_bits})"
+            ),
+            Self::Unmapped { failure } => write!(f, "{failure} (not in the virtual trace)"),
+        }
+    }
+}
+
+/// The result of running the mock prover, as written by `mock --json`.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MockReport {
+    /// Failures of the mock prover mapped to the virtual trace, empty if the circuit does not have one
+    pub failures: Vec<ConstraintFailure>,
+    /// Failures reported by the mock prover, in terms of the columns and rows of the final circuit
+    pub raw_failures: Vec<String>,
+}
+
+impl MockReport {
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        serde_json::to_writer_pretty(file, self).expect("Could not write mock report");
+    }
+}
+
+/// An advice cell of the virtual trace, as written by `mock --dump-witness`.
+#[derive(Clone, Debug, Serialize)]
+pub struct AdviceCellDump {
+    #[serde(flatten)]
+    pub location: CellLocation,
+    pub value: String,
+    /// Whether the basic gate `a + b * c = d` is enabled with this cell as `a`
+    pub gate_enabled: bool,
+    /// Cells that this cell is constrained to equal by copy constraints
+    pub copies: Vec<CellLocation>,
+    /// Constant that this cell is constrained to equal
+    pub constant: Option<String>,
+}
+
+/// A cell queued for a range lookup.
+#[derive(Clone, Debug, Serialize)]
+pub struct LookupCellDump {
+    #[serde(flatten)]
+    pub location: CellLocation,
+    pub value: String,
+}
+
+/// A cell of an instance column, together with the advice cell it is copied from.
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceCellDump {
+    pub column: usize,
+    pub row: usize,
+    pub value: String,
+    pub source: Option<CellLocation>,
+}
+
+/// Every cell of the virtual trace, for debugging.
+#[derive(Clone, Debug, Serialize)]
+pub struct WitnessDump {
+    pub advice: Vec<AdviceCellDump>,
+    pub lookups: Vec<LookupCellDump>,
+    pub instances: Vec<InstanceCellDump>,
+}
+
+impl WitnessDump {
+    /// Writes the dump as JSON or CSV, depending on whether `path` ends in `.json` or `.csv`.
+    ///
+    /// The CSV has one row per cell, where the `kind` column is one of `advice`, `lookup` or `instance`.
+    /// Copy constraints are written as `phase:thread:offset` separated by `;`.
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => {
+                serde_json::to_writer_pretty(file, self).expect("Could not write witness dump")
+            }
+            Some("csv") => {
+                self.write_csv(BufWriter::new(file)).expect("Could not write witness dump")
+            }
+            _ => panic!("Witness dump path {path:?} should end in .json or .csv"),
+        }
+    }
+
+    fn write_csv(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "kind,phase,thread,offset,value,gate_enabled,copies,constant,instance_column,instance_row"
+        )?;
+        let fmt_location =
+            |loc: &CellLocation| format!("{}:{}:{}", loc.phase, loc.thread, loc.offset);
+        for cell in &self.advice {
+            let CellLocation { phase, thread, offset } = cell.location;
+            let copies = cell.copies.iter().map(fmt_location).collect::<Vec<_>>().join(";");
+            let constant = cell.constant.as_deref().unwrap_or_default();
+            writeln!(
+                writer,
+                "advice,{phase},{thread},{offset},{},{},{copies},{constant},,",
+                cell.value, cell.gate_enabled
+            )?;
+        }
+        for cell in &self.lookups {
+            let CellLocation { phase, thread, offset } = cell.location;
+            writeln!(writer, "lookup,{phase},{thread},{offset},{},,,,,", cell.value)?;
+        }
+        for cell in &self.instances {
+            let source = cell
+                .source
+                .map(|loc| format!("{},{},{}", loc.phase, loc.thread, loc.offset))
+                .unwrap_or_else(|| ",,".to_string());
+            writeln!(writer, "instance,{source},{},,,,{},{}", cell.value, cell.column, cell.row)?;
+        }
+        writer.flush()
+    }
+}
+
+pub struct VirtualTrace<'a> {
+    builder: &'a BaseCircuitBuilder<Fr>,
+    contexts: HashMap<(&'static str, usize), &'a Context<Fr>>,
+}
+
+impl<'a> VirtualTrace<'a> {
+    pub fn new(builder: &'a BaseCircuitBuilder<Fr>) -> Self {
+        let contexts = builder
+            .core()
+            .phase_manager
+            .iter()
+            .flat_map(|phase_manager| &phase_manager.threads)
+            .map(|ctx| ((ctx.type_id(), ctx.id()), ctx))
+            .collect();
+        Self { builder, contexts }
+    }
+
+    pub fn builder(&self) -> &'a BaseCircuitBuilder<Fr> {
+        self.builder
+    }
+
+    /// All threads of the builder, ordered by phase and then by thread id.
+    pub fn threads(&self) -> impl Iterator<Item = &'a Context<Fr>> {
+        self.builder.core().phase_manager.iter().flat_map(|phase_manager| &phase_manager.threads)
+    }
+
+    /// Returns `None` if the cell was not assigned in one of the builder's threads, for example if it belongs to a lookup table.
+    pub fn location(&self, cell: ContextCell) -> Option<CellLocation> {
+        let ctx = self.contexts.get(&(cell.type_id, cell.context_id))?;
+        (cell.offset < ctx.advice.len()).then_some(CellLocation {
+            phase: ctx.phase(),
+            thread: cell.context_id,
+            offset: cell.offset,
+        })
+    }
+
+    pub fn value(&self, cell: ContextCell) -> Option<Fr> {
+        let ctx = self.contexts.get(&(cell.type_id, cell.context_id))?;
+        ctx.advice.get(cell.offset).map(|value| value.evaluate())
+    }
+
+    /// Collects every advice cell of every thread, together with the cells queued for range lookups and the instance cells.
+    pub fn witness_dump(&self) -> WitnessDump {
+        let mut copies = HashMap::<CellLocation, Vec<CellLocation>>::new();
+        let mut constants = HashMap::new();
+        {
+            let copy_manager = self.builder.core().copy_manager.lock().unwrap();
+            for (left, right) in &copy_manager.advice_equalities {
+                if let (Some(left), Some(right)) = (self.location(*left), self.location(*right)) {
+                    copies.entry(left).or_default().push(right);
+                    copies.entry(right).or_default().push(left);
+                }
+            }
+            for (constant, cell) in &copy_manager.constant_equalities {
+                if let Some(loc) = self.location(*cell) {
+                    constants.insert(loc, encode_fr(constant, InstanceFormat::Hex));
+                }
+            }
+        }
+
+        let mut advice = vec![];
+        for ctx in self.threads() {
+            for (offset, value) in ctx.advice.iter().enumerate() {
+                let location = CellLocation { phase: ctx.phase(), thread: ctx.id(), offset };
+                advice.push(AdviceCellDump {
+                    location,
+                    value: encode_fr(&value.evaluate(), InstanceFormat::Hex),
+                    gate_enabled: ctx.selector.get(offset).copied().unwrap_or(false),
+                    copies: copies.remove(&location).unwrap_or_default(),
+                    constant: constants.remove(&location),
+                });
+            }
+        }
+        let lookups = self
+            .builder
+            .lookup_manager()
+            .iter()
+            .flat_map(|lookup_manager| lookup_manager.cells_to_lookup.lock().unwrap().clone())
+            .filter_map(|[cell]| {
+                let location = self.location(cell.cell?)?;
+                Some(LookupCellDump {
+                    location,
+                    value: encode_fr(cell.value(), InstanceFormat::Hex),
+                })
+            })
+            .collect();
+        let instances = self
+            .builder
+            .assigned_instances
+            .iter()
+            .enumerate()
+            .flat_map(|(column, cells)| {
+                cells.iter().enumerate().map(move |(row, cell)| InstanceCellDump {
+                    column,
+                    row,
+                    value: encode_fr(cell.value(), InstanceFormat::Hex),
+                    source: cell.cell.and_then(|cell| self.location(cell)),
+                })
+            })
+            .collect();
+        WitnessDump { advice, lookups, instances }
+    }
+
+    /// Checks the basic gates, copy constraints and range lookups of every thread.
+    ///
+    /// Gates that run past the end of a thread depend on how the threads are laid out in columns, so only
+    /// [Self::map_failures] finds those.
+    pub fn check(&self) -> Vec<ConstraintFailure> {
+        let mut failures = self.check_gates();
+        failures.extend(self.check_copy_constraints());
+        failures.extend(self.check_lookups());
+        failures
+    }
+
+    /// Maps the failures reported by the mock prover on the synthesized circuit to the virtual trace.
+    ///
+    /// A gate failure is mapped through the cells the builder assigned at its row, so gates that run past the end of a
+    /// thread into the next one are found as well. Copy constraints and lookups are checked on the virtual trace as in
+    /// [Self::check]. Any failure that is not accounted for either way is kept as [ConstraintFailure::Unmapped].
+    pub fn map_failures(&self, raw_failures: &[VerifyFailure]) -> Vec<ConstraintFailure> {
+        let layout = self.layout();
+        let mut failures = vec![];
+        let mut unmapped = vec![];
+        for raw_failure in raw_failures {
+            let gate_failures = match raw_failure {
+                VerifyFailure::ConstraintNotSatisfied { location, .. } => {
+                    self.gate_failures_at(&layout, failure_row(location))
+                }
+                _ => vec![],
+            };
+            if gate_failures.is_empty() {
+                unmapped.push(raw_failure);
+            }
+            for failure in gate_failures {
+                if !failures.contains(&failure) {
+                    failures.push(failure);
+                }
+            }
+        }
+        let copy_failures = self.check_copy_constraints();
+        let lookup_failures = self.check_lookups();
+        unmapped.retain(|raw_failure| match raw_failure {
+            VerifyFailure::Permutation { .. } => copy_failures.is_empty(),
+            VerifyFailure::Lookup { .. } => lookup_failures.is_empty(),
+            _ => true,
+        });
+        failures.extend(copy_failures);
+        failures.extend(lookup_failures);
+        failures.extend(
+            unmapped.into_iter().map(|raw_failure| ConstraintFailure::Unmapped {
+                failure: raw_failure.to_string(),
+            }),
+        );
+        failures
+    }
+
+    /// The virtual cell assigned at each advice column and row (relative to its region) of the synthesized circuit.
+    fn layout(&self) -> HashMap<(usize, usize), ContextCell> {
+        let copy_manager = self.builder.core().copy_manager.lock().unwrap();
+        copy_manager
+            .assigned_advices
+            .iter()
+            .map(|(ctx_cell, cell)| ((cell.column.index(), cell.row_offset), *ctx_cell))
+            .collect()
+    }
+
+    /// The basic gates enabled at `row` of any column that are not satisfied by the cells laid out below them.
+    fn gate_failures_at(
+        &self,
+        layout: &HashMap<(usize, usize), ContextCell>,
+        row: usize,
+    ) -> Vec<ConstraintFailure> {
+        let mut gates: Vec<_> = layout
+            .iter()
+            .filter(|((_, cell_row), _)| *cell_row == row)
+            .map(|((column, _), cell)| (*column, *cell))
+            .collect();
+        gates.sort_by_key(|(column, _)| *column);
+        let mut failures = vec![];
+        for (column, cell) in gates {
+            let Some(ctx) = self.contexts.get(&(cell.type_id, cell.context_id)) else { continue };
+            if !ctx.selector.get(cell.offset).copied().unwrap_or(false) {
+                continue;
+            }
+            let values = [0, 1, 2, 3]
+                .map(|i| layout.get(&(column, row + i)).and_then(|cell| self.value(*cell)));
+            let [Some(a), Some(b), Some(c), Some(d)] = values else { continue };
+            if a + b * c != d {
+                failures.push(ConstraintFailure::Gate {
+                    cell: self.location(cell).unwrap(),
+                    values: [a, b, c, d].map(|x| encode_fr(&x, InstanceFormat::Hex)),
+                });
+            }
+        }
+        failures
+    }
+
+    fn check_gates(&self) -> Vec<ConstraintFailure> {
+        let mut failures = vec![];
+        for ctx in self.threads() {
+            for (offset, _) in ctx.selector.iter().enumerate().filter(|(_, enabled)| **enabled) {
+                // gates running past the end of a thread are checked by `map_failures`
+                let Some(values) = ctx.advice.get(offset..offset + 4) else { continue };
+                let [a, b, c, d] = [0, 1, 2, 3].map(|i| values[i].evaluate());
+                if a + b * c != d {
+                    let cell = CellLocation { phase: ctx.phase(), thread: ctx.id(), offset };
+                    let values = [a, b, c, d].map(|x| encode_fr(&x, InstanceFormat::Hex));
+                    failures.push(ConstraintFailure::Gate { cell, values });
+                }
+            }
+        }
+        failures
+    }
+
+    fn check_copy_constraints(&self) -> Vec<ConstraintFailure> {
+        let copy_manager = self.builder.core().copy_manager.lock().unwrap();
+        let mut failures = vec![];
+        for (left, right) in &copy_manager.advice_equalities {
+            let (Some(left_loc), Some(right_loc)) = (self.location(*left), self.location(*right))
+            else {
+                continue;
+            };
+            let [left_value, right_value] = [*left, *right].map(|cell| self.value(cell).unwrap());
+            if left_value != right_value {
+                failures.push(ConstraintFailure::Copy {
+                    left: left_loc,
+                    right: right_loc,
+                    left_value: encode_fr(&left_value, InstanceFormat::Hex),
+                    right_value: encode_fr(&right_value, InstanceFormat::Hex),
+                });
+            }
+        }
+        for (constant, cell) in &copy_manager.constant_equalities {
+            let Some(loc) = self.location(*cell) else { continue };
+            let value = self.value(*cell).unwrap();
+            if value != *constant {
+                failures.push(ConstraintFailure::Constant {
+                    cell: loc,
+                    value: encode_fr(&value, InstanceFormat::Hex),
+                    constant: encode_fr(constant, InstanceFormat::Hex),
+                });
+            }
+        }
+        failures
+    }
+
+    fn check_lookups(&self) -> Vec<ConstraintFailure> {
+        let Some(lookup_bits) = self.builder.lookup_bits() else { return vec![] };
+        let mut failures = vec![];
+        for lookup_manager in self.builder.lookup_manager() {
+            for [cell] in lookup_manager.cells_to_lookup.lock().unwrap().iter() {
+                let value = *cell.value();
+                if fe_to_biguint(&value).bits() as usize <= lookup_bits {
+                    continue;
+                }
+                let Some(loc) = cell.cell.and_then(|cell| self.location(cell)) else { continue };
+                failures.push(ConstraintFailure::Lookup {
+                    cell: loc,
+                    value: encode_fr(&value, InstanceFormat::Hex),
+                    lookup_bits,
+                });
+            }
+        }
+        failures
+    }
+}
+
+/// The row of a failure, relative to the region it is in if any.
+pub(crate) fn failure_row(location: &FailureLocation) -> usize {
+    match location {
+        FailureLocation::InRegion { offset, .. } => *offset,
+        FailureLocation::OutsideRegion { row } => *row,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::{
+        gates::circuit::{builder::BaseCircuitBuilder, CircuitBuilderStage},
+        halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr},
+        QuantumCell::{Constant, Existing, Witness},
+    };
+    use snark_verifier_sdk::CircuitExt;
+
+    use super::{CellLocation, ConstraintFailure, VirtualTrace};
+
+    #[test]
+    fn test_virtual_trace_gate_failure() {
+        let mut builder = BaseCircuitBuilder::<Fr>::from_stage(CircuitBuilderStage::Mock);
+        let ctx = builder.main(0);
+        let x = ctx.load_witness(Fr::from(3));
+        // 72 + 3 * 3 != 80
+        ctx.assign_region(
+            [Constant(Fr::from(72)), Existing(x), Existing(x), Witness(Fr::from(80))],
+            [0],
+        );
+        let failures = VirtualTrace::new(&builder).check();
+        assert_eq!(failures.len(), 1);
+        let location = CellLocation { phase: 0, thread: 0, offset: 1 };
+        assert!(matches!(&failures[0], ConstraintFailure::Gate { cell, .. } if *cell == location));
+        builder.clear();
+    }
+
+    #[test]
+    fn test_map_gate_failure_across_threads() {
+        let k = 8;
+        let mut builder = BaseCircuitBuilder::<Fr>::from_stage(CircuitBuilderStage::Mock).use_k(k);
+        // the gate enabled at the last cell of the main thread reads the cells of the next thread: 1 + 2 * 3 != 8
+        builder.main(0).assign_region([Witness(Fr::from(1))], [0]);
+        builder
+            .pool(0)
+            .new_thread()
+            .assign_region([Witness(Fr::from(2)), Witness(Fr::from(3)), Witness(Fr::from(8))], []);
+        builder.calculate_params(Some(9));
+        let raw_failures =
+            MockProver::run(k as u32, &builder, builder.instances()).unwrap().verify().unwrap_err();
+        let trace = VirtualTrace::new(&builder);
+        assert!(trace.check().is_empty(), "the gate runs past the end of its thread");
+        let failures = trace.map_failures(&raw_failures);
+        assert_eq!(failures.len(), 1, "{failures:?}");
+        let location = CellLocation { phase: 0, thread: 0, offset: 0 };
+        assert!(matches!(&failures[0], ConstraintFailure::Gate { cell, .. } if *cell == location));
+        builder.clear();
+    }
+
+    #[test]
+    fn test_witness_dump() {
+        let mut builder = BaseCircuitBuilder::<Fr>::from_stage(CircuitBuilderStage::Mock);
+        let ctx = builder.main(0);
+        let x = ctx.load_witness(Fr::from(3));
+        let out = ctx.assign_region_last(
+            [Constant(Fr::from(72)), Existing(x), Existing(x), Witness(Fr::from(81))],
+            [0],
+        );
+        builder.assigned_instances = vec![vec![x, out]];
+        let dump = VirtualTrace::new(&builder).witness_dump();
+        assert_eq!(dump.advice.len(), 5);
+        assert!(dump.advice[1].gate_enabled);
+        let location = |offset| CellLocation { phase: 0, thread: 0, offset };
+        assert_eq!(dump.advice[0].copies, vec![location(2), location(3)]);
+        assert_eq!(dump.advice[1].constant.as_deref(), Some(dump.advice[1].value.as_str()));
+        assert_eq!(dump.instances[1].source, Some(location(4)));
+
+        let mut csv = vec![];
+        dump.write_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 1 + 5 + 2);
+        builder.clear();
+    }
+}
@@ -0,0 +1,153 @@
+//! Runs circuits written with the raw Halo2 API, such as the ones in [crate::vanilla_circuits], through the scaffold CLI.
+use std::fmt::Debug;
+
+use halo2_base::{
+    gates::flex_gate::MultiPhaseThreadBreakPoints,
+    halo2_proofs::{
+        circuit::Layouter,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error, Selector},
+    },
+};
+use serde::{de::DeserializeOwned, Serialize};
+use snark_verifier_sdk::CircuitExt;
+
+use super::{call_once, cmd::Cli, read_inputs, run_cli, run_without_inputs, ScaffoldCircuit};
+
+/// Wraps any circuit so that it can be used with [run_cli]. The wrapped circuit is used unchanged for every stage.
+#[derive(Clone, Debug)]
+pub struct VanillaCircuit<C>(pub C);
+
+impl<C: Circuit<Fr>> Circuit<Fr> for VanillaCircuit<C> {
+    type Config = C::Config;
+    type FloorPlanner = C::FloorPlanner;
+    type Params = C::Params;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn params(&self) -> Self::Params {
+        self.0.params()
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<Fr>,
+        params: Self::Params,
+    ) -> Self::Config {
+        C::configure_with_params(meta, params)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+impl<C: CircuitExt<Fr>> CircuitExt<Fr> for VanillaCircuit<C> {
+    fn num_instance(&self) -> Vec<usize> {
+        self.0.num_instance()
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        self.0.instances()
+    }
+
+    fn accumulator_indices() -> Option<Vec<(usize, usize)>> {
+        C::accumulator_indices()
+    }
+
+    fn selectors(config: &Self::Config) -> Vec<Selector> {
+        C::selectors(config)
+    }
+}
+
+impl<C: CircuitExt<Fr>> ScaffoldCircuit for VanillaCircuit<C> {
+    /// Vanilla circuits do not use `halo2-lib` threads, so there is nothing to pin.
+    fn break_points(&self) -> MultiPhaseThreadBreakPoints {
+        vec![]
+    }
+
+    fn clear(&mut self) {}
+}
+
+/// Like [super::run], but for a circuit written with the raw Halo2 API.
+/// `create_circuit` creates the circuit from the private inputs, which are read from the input file like for [super::run].
+///
+/// Instances are taken from [CircuitExt::instances], and [Circuit::params] are pinned at keygen. The params of a vanilla circuit are
+/// determined by `create_circuit`, so when proving they are checked against the pinning instead of being passed in.
+pub fn run_vanilla<T, C>(create_circuit: impl FnOnce(T) -> C, cli: Cli)
+where
+    T: DeserializeOwned,
+    C: CircuitExt<Fr>,
+    C::Params: Clone + Debug + Serialize + DeserializeOwned,
+{
+    if !cli.command.reads_inputs() {
+        return run_vanilla_without_inputs::<T, C>(&create_circuit, cli);
+    }
+    let private_inputs = read_inputs(&cli);
+    run_vanilla_on_inputs(create_circuit, cli, private_inputs)
+}
+
+pub fn run_vanilla_on_inputs<T, C>(create_circuit: impl FnOnce(T) -> C, cli: Cli, private_inputs: T)
+where
+    C: CircuitExt<Fr>,
+    C::Params: Clone + Debug + Serialize + DeserializeOwned,
+{
+    run_cli::<VanillaCircuit<C>>(
+        call_once(&cli.command, |_, pinning, _| {
+            vanilla_circuit(create_circuit(private_inputs), pinning)
+        }),
+        cli,
+    )
+}
+
+/// Like [run_vanilla], but calls `create_circuit` as many times as the command needs, which the `bench` command requires.
+pub fn run_vanilla_repeatable<T, C>(create_circuit: impl Fn(T) -> C, cli: Cli)
+where
+    T: DeserializeOwned + Clone,
+    C: CircuitExt<Fr>,
+    C::Params: Clone + Debug + Serialize + DeserializeOwned,
+{
+    if !cli.command.reads_inputs() {
+        return run_vanilla_without_inputs::<T, C>(&create_circuit, cli);
+    }
+    let private_inputs: T = read_inputs(&cli);
+    run_cli::<VanillaCircuit<C>>(
+        |_, pinning, _| vanilla_circuit(create_circuit(private_inputs.clone()), pinning),
+        cli,
+    )
+}
+
+/// Runs a command that does not need the private inputs on the circuits returned by `create_circuit`, which only determines the circuit type.
+fn run_vanilla_without_inputs<T, C>(_create_circuit: &impl FnOnce(T) -> C, cli: Cli)
+where
+    C: CircuitExt<Fr>,
+    C::Params: Clone + Debug + Serialize + DeserializeOwned,
+{
+    run_without_inputs::<VanillaCircuit<C>>(cli)
+}
+
+/// Wraps `circuit`, checking its params against the `pinning` when proving.
+fn vanilla_circuit<C: CircuitExt<Fr>>(
+    circuit: C,
+    pinning: Option<(C::Params, MultiPhaseThreadBreakPoints)>,
+) -> VanillaCircuit<C>
+where
+    C::Params: Serialize,
+{
+    if let Some((params, _)) = pinning {
+        assert_params_match(&circuit.params(), &params);
+    }
+    VanillaCircuit(circuit)
+}
+
+/// Panics if `params` are not the `pinned` params. `Circuit::Params` need not implement `PartialEq`, so they are compared as JSON.
+fn assert_params_match<P: Serialize>(params: &P, pinned: &P) {
+    let [params, pinned] =
+        [params, pinned].map(|p| serde_json::to_value(p).expect("params should serialize"));
+    assert_eq!(params, pinned, "Circuit params differ from the pinning, run keygen again");
+}
@@ -0,0 +1,205 @@
+//! Native verification of snarks, either one at a time or as a batch sharing a single final pairing check.
+use std::{fs::File, io::BufReader, path::Path};
+
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{verify_proof, Circuit, Error, VerifyingKey},
+    poly::{
+        commitment::ParamsProver,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{VerifierGWC, VerifierSHPLONK},
+            strategy::{AccumulatorStrategy, SingleStrategy},
+        },
+        VerificationStrategy,
+    },
+    SerdeFormat,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use snark_verifier_sdk::{halo2::PoseidonTranscript, NativeLoader, Snark};
+
+use super::pinning::{CircuitPinning, MultiOpenScheme};
+
+/// Verifies snarks with a verifying key and params that are loaded once and kept in memory.
+#[derive(Clone, Debug)]
+pub struct Verifier {
+    params: ParamsKZG<Bn256>,
+    vk: VerifyingKey<G1Affine>,
+    scheme: MultiOpenScheme,
+}
+
+impl Verifier {
+    pub fn new(
+        params: ParamsKZG<Bn256>,
+        vk: VerifyingKey<G1Affine>,
+        scheme: MultiOpenScheme,
+    ) -> Self {
+        Self { params, vk, scheme }
+    }
+
+    /// Reads the verifying key written at keygen for the circuit type `C`, using the multi-open scheme of the pinning.
+    pub fn read<C: Circuit<Fr>>(
+        params: ParamsKZG<Bn256>,
+        pinning_path: impl AsRef<Path>,
+        vk_path: impl AsRef<Path>,
+    ) -> Self
+    where
+        C::Params: Serialize + DeserializeOwned,
+    {
+        let pinning = CircuitPinning::<C::Params>::read(pinning_path);
+        let vk_path = vk_path.as_ref();
+        let f = File::open(vk_path)
+            .unwrap_or_else(|e| panic!("Failed to open file: {vk_path:?}: {e:?}"));
+        let vk = VerifyingKey::read::<_, C>(
+            &mut BufReader::new(f),
+            SerdeFormat::RawBytes,
+            pinning.params,
+        )
+        .expect("Could not read vkey");
+        Self::new(params, vk, pinning.scheme)
+    }
+
+    /// Overrides the multi-open scheme of the pinning.
+    pub fn use_scheme(mut self, scheme: MultiOpenScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn params(&self) -> &ParamsKZG<Bn256> {
+        &self.params
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<G1Affine> {
+        &self.vk
+    }
+
+    pub fn scheme(&self) -> MultiOpenScheme {
+        self.scheme
+    }
+
+    pub fn verify(&self, snark: &Snark) -> Result<(), Error> {
+        verify_snark(&self.params, &self.vk, snark, self.scheme)
+    }
+
+    /// See [batch_verify_snarks].
+    pub fn batch_verify(&self, snarks: &[Snark]) -> Result<(), BatchVerifyError> {
+        batch_verify_snarks(&self.params, &self.vk, snarks, self.scheme)
+    }
+}
+
+/// Verifies a single snark against the verifying key using the given multi-open scheme.
+pub fn verify_snark(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    snark: &Snark,
+    scheme: MultiOpenScheme,
+) -> Result<(), Error> {
+    let verifier_params = params.verifier_params();
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = PoseidonTranscript::<NativeLoader, &[u8]>::new::<0>(&snark.proof[..]);
+    let instances = snark.instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    match scheme {
+        MultiOpenScheme::Shplonk => {
+            verify_proof::<
+                KZGCommitmentScheme<Bn256>,
+                VerifierSHPLONK<'_, Bn256>,
+                _,
+                _,
+                SingleStrategy<'_, Bn256>,
+            >(verifier_params, vk, strategy, &[&instances[..]], &mut transcript)
+        }
+        MultiOpenScheme::Gwc => {
+            verify_proof::<
+                KZGCommitmentScheme<Bn256>,
+                VerifierGWC<'_, Bn256>,
+                _,
+                _,
+                SingleStrategy<'_, Bn256>,
+            >(verifier_params, vk, strategy, &[&instances[..]], &mut transcript)
+        }
+    }
+}
+
+/// Runs the verifier on `snark` without the final pairing check, adding its KZG opening into `strategy` instead.
+fn accumulate_snark<'params>(
+    params: &'params ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    snark: &Snark,
+    scheme: MultiOpenScheme,
+    strategy: AccumulatorStrategy<'params, Bn256>,
+) -> Result<AccumulatorStrategy<'params, Bn256>, Error> {
+    let verifier_params = params.verifier_params();
+    let mut transcript = PoseidonTranscript::<NativeLoader, &[u8]>::new::<0>(&snark.proof[..]);
+    let instances = snark.instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    match scheme {
+        MultiOpenScheme::Shplonk => {
+            verify_proof::<
+                KZGCommitmentScheme<Bn256>,
+                VerifierSHPLONK<'params, Bn256>,
+                _,
+                _,
+                AccumulatorStrategy<'params, Bn256>,
+            >(verifier_params, vk, strategy, &[&instances[..]], &mut transcript)
+        }
+        MultiOpenScheme::Gwc => {
+            verify_proof::<
+                KZGCommitmentScheme<Bn256>,
+                VerifierGWC<'params, Bn256>,
+                _,
+                _,
+                AccumulatorStrategy<'params, Bn256>,
+            >(verifier_params, vk, strategy, &[&instances[..]], &mut transcript)
+        }
+    }
+}
+
+/// Why a batch of snarks failed [batch_verify_snarks].
+#[derive(Debug)]
+pub enum BatchVerifyError {
+    /// The snarks at these indices into `snarks` do not verify on their own.
+    Invalid(Vec<(usize, Error)>),
+    /// The final pairing check of the batch failed although every snark verifies on its own, which a correct verifier never does.
+    Inconsistent,
+}
+
+/// Verifies many snarks for the same verifying key by accumulating all of their KZG openings and doing a single final pairing check.
+///
+/// If the batch check fails, every snark is verified individually to find the invalid ones.
+pub fn batch_verify_snarks(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    snarks: &[Snark],
+    scheme: MultiOpenScheme,
+) -> Result<(), BatchVerifyError> {
+    let mut strategy = Some(AccumulatorStrategy::new(params));
+    for snark in snarks {
+        // a malformed transcript consumes the strategy, in which case we go straight to individual checks
+        strategy = strategy.and_then(|s| accumulate_snark(params, vk, snark, scheme, s).ok());
+    }
+    let finalize = |s: AccumulatorStrategy<'_, Bn256>| match scheme {
+        MultiOpenScheme::Shplonk => VerificationStrategy::<
+            '_,
+            KZGCommitmentScheme<Bn256>,
+            VerifierSHPLONK<'_, Bn256>,
+        >::finalize(s),
+        MultiOpenScheme::Gwc => {
+            VerificationStrategy::<'_, KZGCommitmentScheme<Bn256>, VerifierGWC<'_, Bn256>>::finalize(
+                s,
+            )
+        }
+    };
+    if strategy.map(finalize).unwrap_or(false) {
+        return Ok(());
+    }
+    log::info!("Batch verification failed, verifying {} snarks individually", snarks.len());
+    let failures: Vec<_> = snarks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, snark)| verify_snark(params, vk, snark, scheme).err().map(|e| (i, e)))
+        .collect();
+    if failures.is_empty() {
+        Err(BatchVerifyError::Inconsistent)
+    } else {
+        Err(BatchVerifyError::Invalid(failures))
+    }
+}
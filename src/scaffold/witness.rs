@@ -0,0 +1,174 @@
+//! Witness files, which separate the witness generation of a `halo2-lib` circuit from proving.
+//!
+//! A witness file holds every value that the prover needs besides the proving key: the advice values of each thread, the values queued
+//! for range lookups and the instances. Proving from it does not run the circuit function or read the private inputs.
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use halo2_base::{
+    gates::{
+        circuit::{builder::BaseCircuitBuilder, BaseCircuitParams, CircuitBuilderStage},
+        flex_gate::MultiPhaseThreadBreakPoints,
+    },
+    halo2_proofs::{halo2curves::bn256::Fr, plonk::Assigned},
+    AssignedValue,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WitnessFile {
+    /// The break points the witness was generated with, which must match the pinning of the proving key
+    pub break_points: MultiPhaseThreadBreakPoints,
+    /// Advice values of each thread, in the order of the thread ids
+    pub advice: Vec<Vec<Fr>>,
+    /// Values of the cells queued for range lookups
+    pub lookups: Vec<Fr>,
+    /// Values of each instance column
+    pub instances: Vec<Vec<Fr>>,
+}
+
+fn assigned(value: Fr) -> AssignedValue<Fr> {
+    AssignedValue { value: Assigned::Trivial(value), cell: None }
+}
+
+impl WitnessFile {
+    /// Extracts the witness of `builder` after the circuit function has run.
+    ///
+    /// Returns `None` if the circuit uses the second phase, whose witness depends on challenges that are only known while proving.
+    pub fn new(builder: &BaseCircuitBuilder<Fr>) -> Option<Self> {
+        let core = builder.core();
+        if core.phase_manager.iter().skip(1).any(|phase| !phase.threads.is_empty()) {
+            return None;
+        }
+        let lookup_managers = builder.lookup_manager();
+        if lookup_managers
+            .iter()
+            .skip(1)
+            .any(|lookup_manager| !lookup_manager.cells_to_lookup.lock().unwrap().is_empty())
+        {
+            return None;
+        }
+        let advice = core.phase_manager[0]
+            .threads
+            .iter()
+            .map(|ctx| ctx.advice.iter().map(|value| value.evaluate()).collect())
+            .collect();
+        let lookups = lookup_managers[0]
+            .cells_to_lookup
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|[cell]| *cell.value())
+            .collect();
+        let instances = builder
+            .assigned_instances
+            .iter()
+            .map(|column| column.iter().map(|cell| *cell.value()).collect())
+            .collect();
+        Some(Self { break_points: builder.break_points(), advice, lookups, instances })
+    }
+
+    /// Creates a builder for proving that holds this witness, configured with the pinned `params` and `break_points`.
+    pub fn into_builder(
+        self,
+        params: BaseCircuitParams,
+        break_points: MultiPhaseThreadBreakPoints,
+    ) -> BaseCircuitBuilder<Fr> {
+        assert_eq!(
+            self.break_points, break_points,
+            "The witness was generated with different break points than the pinning"
+        );
+        let mut builder = BaseCircuitBuilder::from_stage(CircuitBuilderStage::Prover);
+        builder.set_params(params);
+        builder.set_break_points(break_points);
+        for advice in self.advice {
+            let ctx = builder.pool(0).new_thread();
+            ctx.advice.extend(advice.into_iter().map(Assigned::Trivial));
+        }
+        builder.lookup_manager()[0]
+            .cells_to_lookup
+            .lock()
+            .unwrap()
+            .extend(self.lookups.into_iter().map(|value| [assigned(value)]));
+        builder.assigned_instances = self
+            .instances
+            .into_iter()
+            .map(|column| column.into_iter().map(assigned).collect())
+            .collect();
+        builder
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let file = File::open(path).unwrap_or_else(|_| panic!("Witness not found at {path:?}"));
+        bincode::deserialize_from(BufReader::new(file)).expect("Could not read witness")
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let file =
+            File::create(path).unwrap_or_else(|_| panic!("Could not create file at {path:?}"));
+        bincode::serialize_into(BufWriter::new(file), self).expect("Could not write witness");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use halo2_base::{
+        gates::{
+            circuit::{builder::BaseCircuitBuilder, CircuitBuilderStage},
+            RangeInstructions,
+        },
+        halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr},
+    };
+    use snark_verifier_sdk::CircuitExt;
+
+    use super::WitnessFile;
+
+    #[test]
+    fn test_witness_round_trip() {
+        let k = 8;
+        let f = |builder: &mut BaseCircuitBuilder<Fr>| {
+            let range = builder.range_chip();
+            let ctx = builder.main(0);
+            let x = ctx.load_witness(Fr::from(200));
+            range.range_check(ctx, x, 8);
+            builder.assigned_instances[0].push(x);
+        };
+
+        let mut keygen = BaseCircuitBuilder::<Fr>::from_stage(CircuitBuilderStage::Keygen)
+            .use_k(k)
+            .use_lookup_bits(4)
+            .use_instance_columns(1);
+        f(&mut keygen);
+        let params = keygen.calculate_params(Some(9));
+        // synthesizing the circuit computes the break points
+        MockProver::run(k as u32, &keygen, keygen.instances()).unwrap().assert_satisfied();
+        let break_points = keygen.break_points();
+        keygen.clear();
+
+        let mut prover = BaseCircuitBuilder::<Fr>::from_stage(CircuitBuilderStage::Prover)
+            .use_params(params.clone())
+            .use_break_points(break_points.clone());
+        f(&mut prover);
+        let witness = WitnessFile::new(&prover).unwrap();
+        prover.clear();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.witness");
+        witness.write(&path);
+        let read = WitnessFile::read(&path);
+        assert_eq!(read, witness);
+        assert_eq!(read.instances, vec![vec![Fr::from(200)]]);
+
+        let num_lookups = read.lookups.len();
+        assert!(num_lookups > 0);
+        let mut builder = read.into_builder(params, break_points);
+        assert_eq!(builder.instances(), vec![vec![Fr::from(200)]]);
+        assert_eq!(builder.lookup_manager()[0].cells_to_lookup.lock().unwrap().len(), num_lookups);
+        builder.clear();
+    }
+}
@@ -1,9 +1,10 @@
-use halo2_proofs::{
+use halo2_base::halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
-    halo2curves::FieldExt,
+    halo2curves::{bn256::Fr, ff::PrimeField},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
     poly::Rotation,
 };
+use snark_verifier_sdk::CircuitExt;
 
 #[derive(Clone, Copy, Debug)]
 pub struct IsZeroConfig {
@@ -24,9 +25,9 @@ pub struct IsZeroConfig {
 // MUL_0:    s_i * (x_i * out_i) = 0
 
 impl IsZeroConfig {
-    // it is standard practice to define everything where numbers are in a generic prime field `F` (`FieldExt` are the traits of a prime field)
+    // it is standard practice to define everything where numbers are in a generic prime field `F` (`PrimeField` are the traits of a prime field)
     // `meta` is provided by the halo2 backend, it is the api for specifying PLONKish arithmetization grid shape + storing circuit constraints in polynomial form
-    pub fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> Self {
         let [x, y, out] = [(); 3].map(|_| meta.advice_column());
         let selector = meta.selector();
 
@@ -46,7 +47,7 @@ impl IsZeroConfig {
 
             // specify all polynomial expressions that we require to equal zero
             // `Expression` is basically an abstract container for the polynomial corresponding to a column; in particular it can't implement `Copy` so we need to clone it to pass rust ownership rules
-            vec![s.clone() * (xy + out.clone() - Expression::Constant(F::one())), s * x * out]
+            vec![s.clone() * (xy + out.clone() - Expression::Constant(F::ONE)), s * x * out]
         });
 
         Self { x, y, out, selector }
@@ -58,26 +59,21 @@ impl IsZeroConfig {
 // slightly counterintuitive since the ZKCircuit is only created once, but it is then run multiple times with different inputs
 // you should think that during actual ZKCircuit creation, these are just placeholders for the actual inputs
 #[derive(Clone, Default)]
-pub struct IsZeroCircuit<F: FieldExt> {
+pub struct IsZeroCircuit<F: PrimeField> {
     // let's say our circuit wants to compute x == 0 ? 1 : 0
     pub x: Value<F>, // Value is a wrapper for rust `Option` with some arithmetic operator overloading
 }
 
 // now we implement the halo2 `Circuit` trait for our struct to actually make it a circuit
-impl<F: FieldExt> Circuit<F> for IsZeroCircuit<F> {
+impl<F: PrimeField> Circuit<F> for IsZeroCircuit<F> {
     type Config = IsZeroConfig; // our earlier config
     type FloorPlanner = SimpleFloorPlanner;
+    // this circuit has no configuration parameters
+    type Params = ();
 
     fn without_witnesses(&self) -> Self {
-        // you don't actually need to implement this if you don't want to
-        unimplemented!()
-        // the intention is you return a version of the circuit inputs where all private inputs are `Value::unknown()` to emphasize they shouldn't be known at circuit creation time
-        /*
-        Self {
-            a: Value::unknown(),
-            b: Value::unknown(),
-        }
-        */
+        // we return a version of the circuit inputs where all private inputs are `Value::unknown()` to emphasize they shouldn't be known at circuit creation time
+        Self { x: Value::unknown() }
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
@@ -100,30 +96,23 @@ impl<F: FieldExt> Circuit<F> for IsZeroCircuit<F> {
                 // | 0   | self.x |   |     |          |
 
                 // The API calls witness cells "advice"
-                // For mysterious reasons (weird rust trait issues), this function takes closures
-                let x = region.assign_advice(
-                    || "a | annotation just for debugging",
-                    config.x,
-                    0,
-                    || self.x,
-                )?;
+                // The returned cell borrows `region`, so we only keep its position in the table with `.cell()`
+                let _x = region.assign_advice(config.x, 0, self.x).cell();
                 // by default, cells all have value 0 (except maybe the very last few rows, where there are "blinding factors" for zero knowledge)
 
                 // We need to compute the witness for y = x == 0 ? 1 : x^{-1}
-                // x.value() is of type `Value<F>` which means it can be either the underlying value or None, which leads to ugly code:
-                let y_val =
-                    x.value().map(|x| if x == &F::zero() { F::one() } else { x.invert().unwrap() });
+                // self.x is of type `Value<F>` which means it can be either the underlying value or None, which leads to ugly code:
+                let y_val = self.x.map(|x| if x == F::ZERO { F::ONE } else { x.invert().unwrap() });
                 // we assign this to the y column in row 0
                 // | row | x      | y     | out | selector |
                 // | 0   | self.x | y_val |     |          |
-                let _y = region.assign_advice(|| "y", config.y, 0, || y_val)?;
+                let _y = region.assign_advice(config.y, 0, y_val).cell();
 
                 // Entirely separately we can just compute the witness for out = x == 0 ? 1 : 0 the normal way
-                let out_val = x.value().map(|x| if x == &F::zero() { F::one() } else { F::zero() });
+                let out_val = self.x.map(|x| if x == F::ZERO { F::ONE } else { F::ZERO });
                 // | row | x      | y     | out     | selector |
                 // | 0   | self.x | y_val | out_val |          |
-                let out = region.assign_advice(|| "is_zero out", config.out, 0, || out_val)?;
-                dbg!(&out);
+                let out = region.assign_advice(config.out, 0, out_val).cell();
 
                 // but wait, selector column defaults to all 0s, so no gates are actually turned "on"
                 // we need to turn our ISZERO gate on in row 0 only:
@@ -135,25 +124,83 @@ impl<F: FieldExt> Circuit<F> for IsZeroCircuit<F> {
                 // | row | x      | y     | out     | selector |
                 // | 0   | self.x | y_val | out_val | 1        |
                 // | 1   | out    |       |         |          |
-                out.copy_advice(|| "copy out", &mut region, config.x, 1)?;
-                // this is exactly the same as the following two lines of code:
-                // let out_copy = region.assign_advice(|| "copy out", config.x, 1, || out_val)?;
-                // region.constrain_equal(out.cell(), out_copy.cell())?;
+                // other versions of halo2 write this as `out.copy_advice(|| "copy out", &mut region, config.x, 1)`, which is exactly the same as
+                // the following two lines of code: assign a new cell with the same value and impose an equality (copy) constraint between the two cells
+                let out_copy = region.assign_advice(config.x, 1, out_val).cell();
+                region.constrain_equal(out, out_copy);
                 Ok(())
             },
         )
     }
 }
 
+// `CircuitExt` tells `snark-verifier` about the public instances of the circuit, so it can be run through the scaffold CLI
+impl CircuitExt<Fr> for IsZeroCircuit<Fr> {
+    // this circuit has no instance columns
+    fn num_instance(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        vec![]
+    }
+}
+
 // cfg(test) tells rust to only compile this in test mode
 #[cfg(test)]
 mod test {
-    use halo2_proofs::{
-        arithmetic::Field, circuit::Value, dev::MockProver, halo2curves::bn256::Fr,
+    use halo2_base::halo2_proofs::{
+        arithmetic::Field,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::bn256::Fr,
+        plonk::{Circuit, ConstraintSystem, Error},
     };
     use rand::rngs::OsRng;
 
-    use super::IsZeroCircuit;
+    use super::{IsZeroCircuit, IsZeroConfig};
+    use crate::scaffold::testing::{assert_rejects, ExpectedFailure};
+
+    /// Assigns the same cells as [IsZeroCircuit], except that `out` is chosen by a malicious prover instead of computed from `x`.
+    struct ForgedIsZeroCircuit {
+        x: Value<Fr>,
+        out: Value<Fr>,
+    }
+
+    impl Circuit<Fr> for ForgedIsZeroCircuit {
+        type Config = IsZeroConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self {
+            Self { x: Value::unknown(), out: Value::unknown() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            IsZeroConfig::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "forged IsZero circuit",
+                |mut region| {
+                    let y =
+                        self.x.map(|x| if x == Fr::ZERO { Fr::ONE } else { x.invert().unwrap() });
+                    let _x = region.assign_advice(config.x, 0, self.x).cell();
+                    let _y = region.assign_advice(config.y, 0, y).cell();
+                    let out = region.assign_advice(config.out, 0, self.out).cell();
+                    config.selector.enable(&mut region, 0)?;
+                    let out_copy = region.assign_advice(config.x, 1, self.out).cell();
+                    region.constrain_equal(out, out_copy);
+                    Ok(())
+                },
+            )
+        }
+    }
 
     // this marks the function as a test
     #[test]
@@ -168,9 +215,24 @@ mod test {
     #[test]
     fn test_is_zero_random() {
         let k = 5;
+        let x = Fr::random(OsRng);
         // when actually running a circuit, we specialize F to the scalar field of BN254, denoted Fr
-        let circuit = IsZeroCircuit { x: Value::known(Fr::random(OsRng)) };
+        let circuit = IsZeroCircuit { x: Value::known(x) };
 
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
+
+        // claiming that a nonzero `x` is zero must violate `x * out = 0`
+        let forged = ForgedIsZeroCircuit { x: Value::known(x), out: Value::known(Fr::ONE) };
+        assert_rejects(k, &forged, vec![], &[ExpectedFailure::gate("ISZERO gate").at(0)]);
+    }
+
+    #[test]
+    fn test_is_zero_forged_zero() {
+        let k = 5;
+        // claiming that zero is nonzero must violate `x * y + out = 1`
+        let circuit =
+            ForgedIsZeroCircuit { x: Value::known(Fr::from(0)), out: Value::known(Fr::from(0)) };
+
+        assert_rejects(k, &circuit, vec![], &[ExpectedFailure::gate("ISZERO gate").at(0)]);
     }
 }
@@ -1,9 +1,10 @@
-use halo2_proofs::{
+use halo2_base::halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
-    halo2curves::FieldExt,
+    halo2curves::{bn256::Fr, ff::PrimeField},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
     poly::Rotation,
 };
+use snark_verifier_sdk::CircuitExt;
 
 #[derive(Clone, Copy, Debug)]
 pub struct OrConfig {
@@ -21,9 +22,9 @@ pub struct OrConfig {
 // s_i * (a_i + a_{i+1} - a_i * a_{i+1} - a_{i+2}) = 0 for all i
 
 impl OrConfig {
-    // it is standard practice to define everything where numbers are in a generic prime field `F` (`FieldExt` are the traits of a prime field)
+    // it is standard practice to define everything where numbers are in a generic prime field `F` (`PrimeField` are the traits of a prime field)
     // `meta` is provided by the halo2 backend, it is the api for specifying PLONKish arithmetization grid shape + storing circuit constraints in polynomial form
-    pub fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure<F: PrimeField>(meta: &mut ConstraintSystem<F>) -> Self {
         // create a single witness column
         let witness = meta.advice_column();
         let selector = meta.selector();
@@ -56,7 +57,7 @@ impl OrConfig {
 // slightly counterintuitive since the ZKCircuit is only created once, but it is then run multiple times with different inputs
 // you should think that during actual ZKCircuit creation, these are just placeholders for the actual inputs
 #[derive(Clone, Default)]
-pub struct OrCircuit<F: FieldExt> {
+pub struct OrCircuit<F: PrimeField> {
     // let's say our circuit wants to compute a | b
     // ASSUME that the values of a,b are both in {0,1}
     pub a: Value<F>, // Value is a wrapper for rust `Option` with some arithmetic operator overloading
@@ -64,9 +65,11 @@ pub struct OrCircuit<F: FieldExt> {
 }
 
 // now we implement the halo2 `Circuit` trait for our struct to actually make it a circuit
-impl<F: FieldExt> Circuit<F> for OrCircuit<F> {
+impl<F: PrimeField> Circuit<F> for OrCircuit<F> {
     type Config = OrConfig; // our earlier config
     type FloorPlanner = SimpleFloorPlanner;
+    // this circuit has no configuration parameters
+    type Params = ();
 
     fn without_witnesses(&self) -> Self {
         // you don't actually need to implement this if you don't want to
@@ -101,38 +104,28 @@ impl<F: FieldExt> Circuit<F> for OrCircuit<F> {
                 // | 1   | b       |          |
 
                 // The API calls witness cells "advice"
-                // For mysterious reasons (weird rust trait issues), this function takes closures
-                let a = region.assign_advice(
-                    || "a | annotation just for debugging",
-                    config.witness,
-                    0,
-                    || self.a,
-                )?;
-                let b = region.assign_advice(
-                    || "b | annotation just for debugging",
-                    config.witness,
-                    1,
-                    || self.b,
-                )?;
+                // The returned cell borrows `region`, so we only keep its position in the table with `.cell()`
+                let _a = region.assign_advice(config.witness, 0, self.a).cell();
+                let _b = region.assign_advice(config.witness, 1, self.b).cell();
                 // by default, cells all have value 0 (except maybe the very last few rows, where there are "blinding factors" for zero knowledge)
 
                 // we need to compute the witness for a | b on our own somehow
                 // let's emphasize this can be done in a different way than the gate:
-                // an annoyance: a.value(), b.value() are both `Value<F>` meaning they can be either the value itself or None, so here comes ugly code:
-                let out_val = a.value().zip(b.value()).map(|(a, b)| {
-                    // now a,b are both type &F
+                // an annoyance: self.a, self.b are both `Value<F>` meaning they can be either the value itself or None, so here comes ugly code:
+                let out_val = self.a.zip(self.b).map(|(a, b)| {
+                    // now a,b are both type F
                     let [a, b] = [a, b].map(|x| {
-                        if x == &F::one() {
+                        if x == F::ONE {
                             true
                         } else {
-                            assert_eq!(x, &F::zero()); // this is just an assumption check, not a circuit constraint
+                            assert_eq!(x, F::ZERO); // this is just an assumption check, not a circuit constraint
                             false
                         }
                     });
                     // now a,b are bool
                     let out = a || b;
                     // we return the bool as an F value
-                    F::from(out)
+                    F::from(out as u64)
                 });
                 // out_val is now type `Value<F>`
                 // we put this in row 2:
@@ -140,8 +133,7 @@ impl<F: FieldExt> Circuit<F> for OrCircuit<F> {
                 // | 0   | a       |          |
                 // | 1   | b       |          |
                 // | 2   | a || b  |          |
-                let _out =
-                    region.assign_advice(|| "a OR b output", config.witness, 2, || out_val)?;
+                let _out = region.assign_advice(config.witness, 2, out_val).cell();
 
                 // but wait, selector column defaults to all 0s, so no gates are actually turned "on"
                 // we need to turn our OR gate on in row 0 only:
@@ -155,17 +147,31 @@ impl<F: FieldExt> Circuit<F> for OrCircuit<F> {
                 // For debugging you can also print out the literally cell containing `out_val`:
                 println!("out cell: {_out:?}");
                 // for just the value:
-                // println!("out value: {:?}", _out.value());
+                // println!("out value: {:?}", out_val);
                 Ok(())
             },
         )
     }
 }
 
+// `CircuitExt` tells `snark-verifier` about the public instances of the circuit, so it can be run through the scaffold CLI
+impl CircuitExt<Fr> for OrCircuit<Fr> {
+    // this circuit has no instance columns
+    fn num_instance(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        vec![]
+    }
+}
+
 // cfg(test) tells rust to only compile this in test mode
 #[cfg(test)]
 mod test {
-    use halo2_proofs::{circuit::Value, dev::MockProver, halo2curves::bn256::Fr};
+    use halo2_base::halo2_proofs::{
+        arithmetic::Field, circuit::Value, dev::MockProver, halo2curves::bn256::Fr,
+    };
 
     use super::OrCircuit;
 
@@ -174,7 +180,7 @@ mod test {
     fn test_or() {
         let k = 5;
         // when actually running a circuit, we specialize F to the scalar field of BN254, denoted Fr
-        let circuit = OrCircuit { a: Value::known(Fr::one()), b: Value::known(Fr::one()) };
+        let circuit = OrCircuit { a: Value::known(Fr::ONE), b: Value::known(Fr::ONE) };
 
         MockProver::run(k, &circuit, vec![]).unwrap().assert_satisfied();
     }
@@ -1,15 +1,16 @@
 use std::marker::PhantomData;
 
-use halo2_proofs::{
+use halo2_base::halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
-    halo2curves::FieldExt,
+    halo2curves::{bn256::Fr, ff::PrimeField},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
     poly::Rotation,
 };
+use snark_verifier_sdk::CircuitExt;
 
 #[derive(Clone, Copy)]
-// it is standard practice to define everything where numbers are in a generic prime field `F` (`FieldExt` are the traits of a prime field)
-pub struct StandardPlonkConfig<F: FieldExt> {
+// it is standard practice to define everything where numbers are in a generic prime field `F` (`PrimeField` are the traits of a prime field)
+pub struct StandardPlonkConfig<F: PrimeField> {
     a: Column<Advice>,
     b: Column<Advice>,
     c: Column<Advice>,
@@ -23,7 +24,7 @@ pub struct StandardPlonkConfig<F: FieldExt> {
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt> StandardPlonkConfig<F> {
+impl<F: PrimeField> StandardPlonkConfig<F> {
     pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         // these are the 3 advice columns
         let [a, b, c] = [(); 3].map(|_| meta.advice_column());
@@ -55,13 +56,15 @@ impl<F: FieldExt> StandardPlonkConfig<F> {
 
 // we use the config to make a circuit:
 #[derive(Clone, Default)]
-pub struct StandardPlonk<F: FieldExt> {
+pub struct StandardPlonk<F: PrimeField> {
     pub x: Value<F>,
 }
 
-impl<F: FieldExt> Circuit<F> for StandardPlonk<F> {
+impl<F: PrimeField> Circuit<F> for StandardPlonk<F> {
     type Config = StandardPlonkConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    // this circuit has no configuration parameters
+    type Params = ();
 
     fn without_witnesses(&self) -> Self {
         Self::default()
@@ -79,33 +82,33 @@ impl<F: FieldExt> Circuit<F> for StandardPlonk<F> {
         layouter.assign_region(
             || "",
             |mut region| {
-                // For an explanation of what the rust ? operator does, see https://doc.rust-lang.org/std/result/#the-question-mark-operator-
-                let x = region.assign_advice(
-                    || "add annotation if you want",
-                    config.a,
-                    0,
-                    || self.x,
-                )?;
+                // The returned cell borrows `region`, so we only keep its position in the table with `.cell()`
+                let x = region.assign_advice(config.a, 0, self.x).cell();
                 // by default, cells all have value 0 (except maybe the very last few rows, where there are "blinding factors" for zero knowledge)
 
                 // square x
                 // row 1: | x | x | x^2 | 0 | 0 | -1 | 1 | 0 |
-                x.copy_advice(|| "", &mut region, config.a, 1)?;
-                x.copy_advice(|| "", &mut region, config.b, 1)?;
-                let val = x.value().map(|x| *x * x);
-                region.assign_advice(|| "", config.c, 1, || val)?;
-                region.assign_fixed(|| "", config.q_c, 1, || Value::known(-F::one()))?;
-                region.assign_fixed(|| "", config.q_ab, 1, || Value::known(F::one()))?;
+                // each copy of `x` is a new cell with the same value and an equality (copy) constraint to the original cell
+                for column in [config.a, config.b] {
+                    let x_copy = region.assign_advice(column, 1, self.x).cell();
+                    region.constrain_equal(x, x_copy);
+                }
+                let val = self.x.map(|x| x * x);
+                region.assign_advice(config.c, 1, val);
+                region.assign_fixed(config.q_c, 1, -F::ONE);
+                region.assign_fixed(config.q_ab, 1, F::ONE);
 
                 // x^2 + 72
                 let c = F::from(72);
-                let val = x.value().map(|x| *x * x + c);
-                x.copy_advice(|| "", &mut region, config.a, 2)?;
-                x.copy_advice(|| "", &mut region, config.b, 2)?;
-                region.assign_advice(|| "", config.c, 2, || val)?;
-                region.assign_fixed(|| "", config.q_c, 2, || Value::known(-F::one()))?;
-                region.assign_fixed(|| "", config.q_ab, 2, || Value::known(F::one()))?;
-                region.assign_fixed(|| "", config.constant, 2, || Value::known(c))?;
+                let val = self.x.map(|x| x * x + c);
+                for column in [config.a, config.b] {
+                    let x_copy = region.assign_advice(column, 2, self.x).cell();
+                    region.constrain_equal(x, x_copy);
+                }
+                region.assign_advice(config.c, 2, val);
+                region.assign_fixed(config.q_c, 2, -F::ONE);
+                region.assign_fixed(config.q_ab, 2, F::ONE);
+                region.assign_fixed(config.constant, 2, c);
 
                 Ok(())
             },
@@ -113,9 +116,21 @@ impl<F: FieldExt> Circuit<F> for StandardPlonk<F> {
     }
 }
 
+// `CircuitExt` tells `snark-verifier` about the public instances of the circuit, so it can be run through the scaffold CLI
+impl CircuitExt<Fr> for StandardPlonk<Fr> {
+    // this circuit has no instance columns
+    fn num_instance(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    fn instances(&self) -> Vec<Vec<Fr>> {
+        vec![]
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use halo2_proofs::{
+    use halo2_base::halo2_proofs::{
         arithmetic::Field, circuit::Value, dev::MockProver, halo2curves::bn256::Fr,
     };
     use rand::rngs::OsRng;
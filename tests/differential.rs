@@ -0,0 +1,63 @@
+//! Property based differential tests of the `halo2-lib` examples against native implementations of the same functions.
+use halo2_base::{
+    halo2_proofs::halo2curves::{
+        bn256::Fr,
+        ff::{Field, PrimeField},
+    },
+    utils::fe_to_biguint,
+};
+use halo2_scaffold::scaffold::testing::assert_matches_native;
+use proptest::prelude::*;
+use snark_verifier_sdk::{
+    halo2::OptimizedPoseidonSpec, snark_verifier::util::hash::Poseidon, NativeLoader,
+};
+
+#[allow(dead_code)]
+#[path = "../examples/poseidon.rs"]
+mod poseidon;
+#[allow(dead_code)]
+#[path = "../examples/range.rs"]
+mod range;
+
+const K: u32 = 10;
+// the same for every test, since tests share the process environment
+const LOOKUP_BITS: &str = "8";
+
+/// Field elements spread over the whole field, as `hi * 2^128 + lo`
+fn fr() -> impl Strategy<Value = Fr> {
+    (any::<u128>(), any::<u128>()).prop_map(|(hi, lo)| {
+        Fr::from_u128(hi) * Fr::from_u128(1 << 64).square() + Fr::from_u128(lo)
+    })
+}
+
+fn native_poseidon(input: poseidon::CircuitInput) -> Option<Vec<Vec<Fr>>> {
+    let [x, y] = input.inputs.map(|x| Fr::from_str_vartime(&x).unwrap());
+    let spec = OptimizedPoseidonSpec::<Fr, 3, 2>::new::<8, 57, 0>();
+    let mut hasher = Poseidon::<Fr, Fr, 3, 2>::from_spec(&NativeLoader, spec);
+    hasher.update(&[x, y]);
+    Some(vec![vec![x, y, hasher.squeeze()]])
+}
+
+/// `x` is public and must be less than `2^64`
+fn native_range(input: range::CircuitInput) -> Option<Vec<Vec<Fr>>> {
+    let x = input.x.parse::<u128>().unwrap();
+    (x < 1 << 64).then(|| vec![vec![Fr::from_u128(x)]])
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn test_poseidon_matches_native(x in fr(), y in fr()) {
+        std::env::set_var("LOOKUP_BITS", LOOKUP_BITS);
+        let inputs = [x, y].map(|x| fe_to_biguint(&x).to_string());
+        assert_matches_native(K, poseidon::hash_two, native_poseidon, poseidon::CircuitInput { inputs });
+    }
+
+    #[test]
+    fn test_range_matches_native(x in any::<u128>()) {
+        std::env::set_var("LOOKUP_BITS", LOOKUP_BITS);
+        let input = range::CircuitInput { x: x.to_string() };
+        assert_matches_native(K, range::some_algorithm_in_zk, native_range, input);
+    }
+}
@@ -0,0 +1,230 @@
+//! Runs every command of the proving lifecycle on the `halo2-lib` examples, in a temporary directory.
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+    sync::Once,
+};
+
+use clap::Parser;
+use halo2_base::{
+    gates::circuit::{builder::BaseCircuitBuilder, BaseCircuitParams},
+    halo2_proofs::{
+        halo2curves::{
+            bn256::{Fr, G1Affine},
+            ff::Field,
+        },
+        plonk::VerifyingKey,
+        SerdeFormat,
+    },
+    utils::fs::gen_srs,
+    AssignedValue,
+};
+use halo2_scaffold::scaffold::{
+    circuit_fn,
+    cmd::Cli,
+    pinning::{CircuitPinning, MultiOpenScheme},
+    prove::Prover,
+    run, run_on_inputs,
+    verify::{verify_snark, Verifier},
+};
+use serde::de::DeserializeOwned;
+use snark_verifier_sdk::halo2::read_snark;
+
+#[allow(dead_code)]
+#[path = "../examples/builder.rs"]
+mod builder;
+#[allow(dead_code)]
+#[path = "../examples/halo2_lib.rs"]
+mod halo2_lib;
+#[allow(dead_code)]
+#[path = "../examples/poseidon.rs"]
+mod poseidon;
+#[allow(dead_code)]
+#[path = "../examples/range.rs"]
+mod range;
+
+const K: u32 = 10;
+// the same for every test, since tests share the process environment
+const LOOKUP_BITS: &str = "8";
+
+static SRS: Once = Once::new();
+
+fn setup() {
+    std::env::set_var("LOOKUP_BITS", LOOKUP_BITS);
+    // `gen_srs` writes the params to a file shared by all tests, so it must not run concurrently.
+    // The env var is shared by all tests as well, so the params go to the temporary directory cargo provides for the whole test binary
+    SRS.call_once(|| {
+        let params_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("params");
+        fs::create_dir_all(&params_dir).unwrap();
+        std::env::set_var("PARAMS_DIR", &params_dir);
+        gen_srs(K);
+    });
+}
+
+/// The CLI arguments for circuit `name` with configs and data in `dir`, followed by `args`.
+fn cli(name: &str, dir: &Path, args: &[&str]) -> Cli {
+    let k = K.to_string();
+    let configs = dir.join("configs");
+    let data = dir.join("data");
+    let mut cli_args = vec![
+        "test",
+        "--name",
+        name,
+        "-k",
+        &k,
+        "--config-path",
+        configs.to_str().unwrap(),
+        "--data-path",
+        data.to_str().unwrap(),
+    ];
+    cli_args.extend(args);
+    Cli::parse_from(cli_args)
+}
+
+/// Runs mock, keygen, prove and verify on `f`, then checks that the verifier rejects a tampered snark.
+fn test_lifecycle<T: DeserializeOwned + Clone>(
+    name: &str,
+    f: impl Fn(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>) + Copy,
+    private_inputs: T,
+) {
+    setup();
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    for command in ["mock", "keygen", "prove", "verify"] {
+        run_on_inputs(f, cli(name, dir, &[command]), private_inputs.clone());
+    }
+
+    let pinning_path = dir.join("configs").join(format!("{name}.json"));
+    let [pk_path, vk_path, snark_path] =
+        ["pk", "vk", "snark"].map(|ext| dir.join("data").join(format!("{name}.{ext}")));
+    for path in [&pinning_path, &pk_path, &vk_path, &snark_path] {
+        assert!(path.exists(), "{path:?} was not written");
+    }
+
+    let params = gen_srs(K);
+    let pinning = CircuitPinning::<BaseCircuitParams>::read(&pinning_path);
+    let vk = VerifyingKey::<G1Affine>::read::<_, BaseCircuitBuilder<Fr>>(
+        &mut BufReader::new(File::open(&vk_path).unwrap()),
+        SerdeFormat::RawBytes,
+        pinning.params,
+    )
+    .unwrap();
+    let snark = read_snark(&snark_path).unwrap();
+    verify_snark(&params, &vk, &snark, pinning.scheme).expect("snark should verify");
+
+    let mut tampered = snark.clone();
+    // circuits without instance cells have no instance to tamper with
+    if let Some(x) = tampered.instances.first_mut().and_then(|column| column.first_mut()) {
+        *x += Fr::ONE;
+        assert!(
+            verify_snark(&params, &vk, &tampered, pinning.scheme).is_err(),
+            "snark with a tampered instance should not verify"
+        );
+    }
+
+    let mut tampered = snark;
+    let i = tampered.proof.len() / 2;
+    tampered.proof[i] ^= 1;
+    assert!(
+        verify_snark(&params, &vk, &tampered, pinning.scheme).is_err(),
+        "snark with a tampered proof should not verify"
+    );
+}
+
+/// With `--seed` the SRS, keys and proof are the same on every run.
+#[test]
+fn test_seeded_reproducible() {
+    setup();
+    let name = "halo2_lib";
+    let input = halo2_lib::CircuitInput { x: "12".to_string() };
+    let runs = [(); 2].map(|_| {
+        let dir = tempfile::tempdir().unwrap();
+        for command in ["keygen", "prove", "verify"] {
+            let cli = cli(name, dir.path(), &["--seed", "1", command]);
+            run_on_inputs(halo2_lib::some_algorithm_in_zk, cli, input.clone());
+        }
+        ["vk", "snark"]
+            .map(|ext| fs::read(dir.path().join("data").join(format!("{name}.{ext}"))).unwrap())
+    });
+    assert_eq!(runs[0][0], runs[1][0], "verifying keys differ");
+    assert_eq!(runs[0][1], runs[1][1], "snarks differ");
+}
+
+/// `prove --witness` proves from the witness file written by `witness`, without reading the private inputs.
+#[test]
+fn test_prove_from_witness() {
+    setup();
+    let name = "range";
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    let input = range::CircuitInput { x: "18446744073709551615".to_string() };
+    for command in ["keygen", "witness"] {
+        run_on_inputs(range::some_algorithm_in_zk, cli(name, dir, &[command]), input.clone());
+    }
+    let witness_path = dir.join("data").join(format!("{name}.witness"));
+    assert!(witness_path.exists(), "{witness_path:?} was not written");
+
+    // there is no input file for `range_witness`, so `run` would fail if it read the inputs
+    let args =
+        ["--input", "range_witness.in", "prove", "--witness", witness_path.to_str().unwrap()];
+    run(range::some_algorithm_in_zk, cli(name, dir, &args));
+    run_on_inputs(range::some_algorithm_in_zk, cli(name, dir, &["verify"]), input);
+}
+
+/// The library API keeps the keys in memory and proves many inputs, and writes keys that can be read back.
+#[test]
+fn test_prover_verifier() {
+    setup();
+    let input = |x: u64| halo2_lib::CircuitInput { x: x.to_string() };
+    let prover = Prover::keygen(
+        circuit_fn(halo2_lib::some_algorithm_in_zk),
+        gen_srs(K),
+        input(0),
+        MultiOpenScheme::Shplonk,
+    );
+    let verifier = prover.verifier();
+    let snarks = [12, 13].map(|x| prover.prove(input(x)));
+    for snark in &snarks {
+        verifier.verify(snark).expect("snark should verify");
+    }
+    verifier.batch_verify(&snarks).expect("snarks should verify as a batch");
+
+    let dir = tempfile::tempdir().unwrap();
+    let [pinning_path, pk_path, vk_path] =
+        ["json", "pk", "vk"].map(|ext| dir.path().join(format!("halo2_lib.{ext}")));
+    prover.write(&pinning_path, &pk_path, &vk_path);
+    let prover = Prover::<BaseCircuitBuilder<Fr>, _>::read(
+        circuit_fn(halo2_lib::some_algorithm_in_zk),
+        gen_srs(K),
+        &pinning_path,
+        &pk_path,
+    );
+    let verifier = Verifier::read::<BaseCircuitBuilder<Fr>>(gen_srs(K), &pinning_path, &vk_path);
+    let snark = prover.prove(input(14));
+    verifier.verify(&snark).expect("snark should verify");
+    verifier.verify(&snarks[0]).expect("snark from before writing the keys should verify");
+}
+
+#[test]
+fn test_halo2_lib() {
+    let input = halo2_lib::CircuitInput { x: "12".to_string() };
+    test_lifecycle("halo2_lib", halo2_lib::some_algorithm_in_zk, input);
+}
+
+#[test]
+fn test_range() {
+    let input = range::CircuitInput { x: "18446744073709551615".to_string() };
+    test_lifecycle("range", range::some_algorithm_in_zk, input);
+}
+
+#[test]
+fn test_poseidon() {
+    let input = poseidon::CircuitInput { inputs: ["6".to_string(), "100".to_string()] };
+    test_lifecycle("poseidon", poseidon::hash_two, input);
+}
+
+#[test]
+fn test_builder() {
+    test_lifecycle("builder", builder::some_algorithm_in_zk, Fr::from(7));
+}
@@ -0,0 +1,107 @@
+//! Snapshot tests of the vk hash, circuit params, cell counts and break points of the example and vanilla circuits at a fixed `k`.
+//!
+//! The snapshots are in `tests/snapshots`. A missing snapshot fails its test, and `UPDATE_SNAPSHOTS=1` writes all of them.
+use std::{path::PathBuf, sync::OnceLock};
+
+use halo2_base::{
+    gates::circuit::{builder::BaseCircuitBuilder, CircuitBuilderStage},
+    halo2_proofs::{
+        circuit::Value,
+        halo2curves::bn256::{Bn256, Fr},
+        poly::kzg::commitment::ParamsKZG,
+    },
+    AssignedValue,
+};
+use halo2_scaffold::{
+    scaffold::{create_circuit, load_params, testing::assert_snapshot, vanilla::VanillaCircuit},
+    vanilla_circuits::{is_zero::IsZeroCircuit, or::OrCircuit, standard_plonk::StandardPlonk},
+};
+use snark_verifier_sdk::CircuitExt;
+
+#[allow(dead_code)]
+#[path = "../examples/builder.rs"]
+mod builder;
+#[allow(dead_code)]
+#[path = "../examples/halo2_lib.rs"]
+mod halo2_lib;
+#[allow(dead_code)]
+#[path = "../examples/poseidon.rs"]
+mod poseidon;
+#[allow(dead_code)]
+#[path = "../examples/range.rs"]
+mod range;
+
+const K: u32 = 10;
+// the same for every test, since tests share the process environment
+const LOOKUP_BITS: &str = "8";
+const SEED: u64 = 0;
+
+static PARAMS: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
+
+fn params() -> &'static ParamsKZG<Bn256> {
+    std::env::set_var("LOOKUP_BITS", LOOKUP_BITS);
+    // `load_params` writes the seeded SRS to a file shared by all tests, so it must not run concurrently
+    PARAMS.get_or_init(|| load_params(K, Some(SEED)))
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(format!("{name}.json"))
+}
+
+fn test_snapshot<T>(
+    name: &str,
+    f: impl FnOnce(&mut BaseCircuitBuilder<Fr>, T, &mut Vec<AssignedValue<Fr>>),
+    private_inputs: T,
+) {
+    let params = params();
+    let circuit = create_circuit(f, private_inputs, CircuitBuilderStage::Keygen, None, params);
+    assert_snapshot(snapshot_path(name), name, params, circuit);
+}
+
+fn test_vanilla_snapshot<C: CircuitExt<Fr>>(name: &str, circuit: C)
+where
+    C::Params: serde::Serialize,
+{
+    assert_snapshot(snapshot_path(name), name, params(), VanillaCircuit(circuit));
+}
+
+#[test]
+fn test_halo2_lib() {
+    let input = halo2_lib::CircuitInput { x: "12".to_string() };
+    test_snapshot("halo2_lib", halo2_lib::some_algorithm_in_zk, input);
+}
+
+#[test]
+fn test_range() {
+    let input = range::CircuitInput { x: "18446744073709551615".to_string() };
+    test_snapshot("range", range::some_algorithm_in_zk, input);
+}
+
+#[test]
+fn test_poseidon() {
+    let input = poseidon::CircuitInput { inputs: ["6".to_string(), "100".to_string()] };
+    test_snapshot("poseidon", poseidon::hash_two, input);
+}
+
+#[test]
+fn test_builder() {
+    test_snapshot("builder", builder::some_algorithm_in_zk, Fr::from(7));
+}
+
+#[test]
+fn test_is_zero() {
+    test_vanilla_snapshot("is_zero", IsZeroCircuit { x: Value::known(Fr::from(3)) });
+}
+
+#[test]
+fn test_or() {
+    test_vanilla_snapshot(
+        "or",
+        OrCircuit { a: Value::known(Fr::from(1)), b: Value::known(Fr::from(0)) },
+    );
+}
+
+#[test]
+fn test_standard_plonk() {
+    test_vanilla_snapshot("standard_plonk", StandardPlonk { x: Value::known(Fr::from(3)) });
+}